@@ -0,0 +1,97 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use macroquad::file::load_file;
+
+/// why an asset couldn't be loaded, reported to the player instead of crashing
+#[derive(Clone, Debug, PartialEq)]
+pub enum AssetError {
+    /// none of the places the assets dir could be had one
+    AssetsDirNotFound { searched: Vec<PathBuf> },
+    /// the file is missing or couldn't be read
+    Read {
+        /// the id from the asset manifest, or what the file is for
+        id: String,
+        path: PathBuf,
+        reason: String,
+    },
+    /// the file was read but isn't something that can be used
+    Decode {
+        /// the id from the asset manifest, or what the file is for
+        id: String,
+        path: PathBuf,
+        reason: String,
+    },
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssetError::AssetsDirNotFound { searched } => {
+                write!(f, "couldn't find the assets dir, searched: ")?;
+                for (i, path) in searched.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", path.display())?;
+                }
+                Ok(())
+            }
+            AssetError::Read { id, path, reason } => {
+                write!(f, "couldn't read {id} from {}: {reason}", path.display())
+            }
+            AssetError::Decode { id, path, reason } => {
+                write!(f, "couldn't decode {id} from {}: {reason}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for AssetError {}
+
+/// reads the whole file, `id` saying what it's for if it can't be
+pub async fn read_asset(id: &str, path: &Path) -> Result<Vec<u8>, AssetError> {
+    load_file(&path.to_string_lossy())
+        .await
+        .map_err(|e| AssetError::Read {
+            id: id.to_string(),
+            path: path.to_path_buf(),
+            reason: e.to_string(),
+        })
+}
+
+/// reads the whole file as UTF-8 text, `id` saying what it's for if it can't be
+pub async fn read_asset_string(id: &str, path: &Path) -> Result<String, AssetError> {
+    let bytes = read_asset(id, path).await?;
+    String::from_utf8(bytes).map_err(|e| AssetError::Decode {
+        id: id.to_string(),
+        path: path.to_path_buf(),
+        reason: e.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let error = AssetError::AssetsDirNotFound {
+            searched: vec![PathBuf::from("a/assets"), PathBuf::from("b/assets")],
+        };
+        assert_eq!(
+            error.to_string(),
+            "couldn't find the assets dir, searched: a/assets, b/assets"
+        );
+
+        let error = AssetError::Read {
+            id: "shoot".to_string(),
+            path: PathBuf::from("assets/sfx/shoot.wav"),
+            reason: "not found".to_string(),
+        };
+        assert_eq!(
+            error.to_string(),
+            "couldn't read shoot from assets/sfx/shoot.wav: not found"
+        );
+    }
+}
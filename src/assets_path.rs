@@ -2,6 +2,8 @@
 use std::env::current_exe;
 use std::path::PathBuf;
 
+use crate::asset_error::AssetError;
+
 pub const ASSETS_DIR: &str = "assets";
 
 /// Returns the PathBuf to the assets folder root
@@ -16,46 +18,39 @@ pub const ASSETS_DIR: &str = "assets";
 ///
 /// WASM does not do anything special.
 ///
-/// Panics if it cannot determine a valid assets dir
+/// Errors with every path it tried if it cannot determine a valid assets dir
 #[cfg(not(target_family = "wasm"))]
-pub fn determine_asset_path() -> PathBuf {
+pub fn determine_asset_path() -> Result<PathBuf, AssetError> {
+    let mut searched = vec![];
+
     // try to find assets dir in cargo project root
-    match std::env::var("CARGO_MANIFEST_DIR") {
-        Ok(cargo_manifest_dir) => {
-            let mut cargo_path = PathBuf::new();
-            cargo_path.push(cargo_manifest_dir);
-            cargo_path.push(ASSETS_DIR);
-            return cargo_path;
-        }
-        Err(_e) => (), // proceed to attempt next way
+    if let Ok(cargo_manifest_dir) = std::env::var("CARGO_MANIFEST_DIR") {
+        searched.push(PathBuf::from(cargo_manifest_dir).join(ASSETS_DIR));
+    }
+
+    if let Ok(exe_path) = current_exe() {
+        // try to find assets dir located next to the executable
+        let mut sibling_path = exe_path.clone();
+        sibling_path.pop();
+        sibling_path.push(ASSETS_DIR);
+        searched.push(sibling_path);
+
+        // try to find assets dir in MacOS bundle (Some.app/Contents/Resources/assets)
+        let mut macos_app_path = exe_path;
+        macos_app_path.pop();
+        macos_app_path.pop();
+        macos_app_path.push("Resources");
+        macos_app_path.push(ASSETS_DIR);
+        searched.push(macos_app_path);
     }
 
-    match current_exe() {
-        Ok(exe_path) => {
-            // try to find assets dir located next to the executable
-            let mut sibling_path = exe_path.clone();
-            sibling_path.pop();
-            sibling_path.push(ASSETS_DIR);
-            if sibling_path.is_dir() {
-                return sibling_path;
-            } else {
-                // try to find assets dir in MacOS bundle (Some.app/Contents/Resources/assets)
-                let mut macos_app_path = exe_path.clone();
-                macos_app_path.pop();
-                macos_app_path.pop();
-                macos_app_path.push("Resources");
-                macos_app_path.push(ASSETS_DIR);
-                if macos_app_path.is_dir() {
-                    return macos_app_path;
-                }
-            }
-        }
-        Err(_e) => (),
+    match searched.iter().find(|path| path.is_dir()) {
+        Some(path) => Ok(path.clone()),
+        None => Err(AssetError::AssetsDirNotFound { searched }),
     }
-    panic!("asset path cannot be determined")
 }
 
 #[cfg(target_family = "wasm")]
-pub fn determine_asset_path() -> PathBuf {
-    PathBuf::from(r"assets")
+pub fn determine_asset_path() -> Result<PathBuf, AssetError> {
+    Ok(PathBuf::from(r"assets"))
 }
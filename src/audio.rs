@@ -1,52 +1,352 @@
+use std::collections::BTreeMap;
 use std::path::Path;
 
-use macroquad::audio::Sound;
+use macroquad::audio::{load_sound_from_bytes, PlaySoundParams, Sound};
+use macroquad::time::get_time;
 
+use crate::asset_error::{read_asset, AssetError};
+use crate::consts::VIRTUAL_WIDTH;
 use crate::context::Context;
+use crate::manifest::SoundEntry;
+use crate::rng::Rng;
+use crate::settings::{Settings, Volume};
 
-pub struct SfxAtlas {
-    pub menu_cancel: Sound,
-    pub menu_select: Sound,
-    pub menu_move: Sound,
-}
+pub mod variants;
+
+use variants::{decode_wav, encode_wav, read_wav, Pcm, Voices, WAV_FORMAT_PCM};
+
+/// played when moving between menu items
+pub const MENU_MOVE: &str = "menu_move";
+/// played when picking a menu item
+pub const MENU_SELECT: &str = "menu_select";
+/// played when backing out of a menu
+pub const MENU_CANCEL: &str = "menu_cancel";
+/// sound effects the code plays as they are, for checking they're in the asset manifest
+pub const SFX: [&str; 3] = [MENU_MOVE, MENU_SELECT, MENU_CANCEL];
+
+pub const SHOOT: &str = "shoot";
+pub const ASTEROID_HIT: &str = "asteroid_hit";
+pub const CRYSTAL: &str = "crystal";
+pub const DEATH: &str = "death";
+pub const WARP: &str = "warp";
+/// sound effects the code plays with variations, for checking they're in the asset manifest as
+/// varied
+pub const VARIED_SFX: [&str; 5] = [SHOOT, ASTEROID_HIT, CRYSTAL, DEATH, WARP];
+
+/// every sound effect in the asset manifest, by id, sorted by how they're played; music tracks
+/// go to the `MusicPlayer` instead
 pub struct AudioAtlas {
-    pub sfx: SfxAtlas,
+    /// played once as they are
+    sfx: BTreeMap<String, Sound>,
+    /// played once with variations
+    varied_sfx: BTreeMap<String, VariedSfx>,
+    /// played in place of sounds that couldn't be loaded
+    silence: Sound,
+}
+
+/// a sound from the asset manifest, ready to be played however its entry says
+pub enum LoadedSound {
+    Sfx(Sound),
+    Varied(VariedSfx),
+    Music(Sound),
 }
 
 impl AudioAtlas {
-    pub async fn new(base_assets_path: &Path) -> Self {
+    /// no sounds yet, they're added as they're loaded
+    pub async fn new() -> Self {
+        Self {
+            sfx: BTreeMap::new(),
+            varied_sfx: BTreeMap::new(),
+            silence: silence().await,
+        }
+    }
+
+    pub fn insert_sfx(&mut self, id: String, sound: Sound) {
+        self.sfx.insert(id, sound);
+    }
+
+    pub fn insert_varied_sfx(&mut self, id: String, varied: VariedSfx) {
+        self.varied_sfx.insert(id, varied);
+    }
+
+    /// the sound played once with the id from the asset manifest, silence if there isn't one
+    pub fn sfx(&self, id: &str) -> &Sound {
+        self.sfx.get(id).unwrap_or(&self.silence)
+    }
+
+    /// the varied sound with the id from the asset manifest, mutable to keep track of its
+    /// voices, silence if there isn't one
+    pub fn varied_sfx_mut(&mut self, id: &str) -> &mut VariedSfx {
+        self.varied_sfx
+            .entry(id.to_string())
+            .or_insert_with(|| VariedSfx::silent(&self.silence))
+    }
+}
+
+/// how much faster or slower each pitch variant plays
+const PITCHES: [f32; 5] = [0.9, 0.95, 1., 1.05, 1.1];
+/// where each stereo variant is placed, from -1 (left) to 1 (right); not all the way to the
+/// sides, so nothing is only heard in one ear
+const PANS: [f32; 5] = [-0.6, -0.3, 0., 0.3, 0.6];
+/// how much quieter a play can randomly be, as a fraction of the full volume
+const VOLUME_VARIATION: f32 = 0.2;
+
+/// a sound effect pre-rendered at a few pitches and stereo positions, since macroquad can only
+/// change a sound's volume, so repeated plays don't all sound the same
+pub struct VariedSfx {
+    /// indexed by pitch, then by pan, like `PITCHES` and `PANS`
+    variants: Vec<Sound>,
+    /// how long each pitch variant lasts, in seconds
+    durations: Vec<f32>,
+    voices: Voices,
+    rng: Rng,
+}
+
+impl VariedSfx {
+    /// renders every variant of the 16-bit PCM WAV file, allowing up to `max_voices` copies to
+    /// play at once
+    ///
+    /// Errors if the file can't be read or isn't 16-bit PCM in mono or stereo
+    pub async fn load(id: &str, path: &Path, max_voices: usize) -> Result<Self, AssetError> {
+        let bytes = read_asset(id, path).await?;
+        let pcm = decode_wav(&bytes).map_err(|reason| AssetError::Decode {
+            id: id.to_string(),
+            path: path.to_path_buf(),
+            reason,
+        })?;
+
+        let mut variants = vec![];
+        let mut durations = vec![];
+        for pitch in PITCHES {
+            for pan in PANS {
+                let variant = pcm.variant(pitch, pan);
+                let sound = load_sound_from_bytes(&encode_wav(&variant)).await.unwrap();
+                variants.push(sound);
+            }
+            durations.push(pcm.duration() / pitch);
+        }
+
+        Ok(Self {
+            variants,
+            durations,
+            voices: Voices::new(max_voices),
+            rng: Rng::new(Rng::seed_from_time()),
+        })
+    }
+
+    /// plays `silence` for every variant, in place of a sound that couldn't be loaded
+    fn silent(silence: &Sound) -> Self {
         Self {
-            sfx: SfxAtlas {
-                menu_cancel: macroquad::audio::load_sound(
-                    base_assets_path
-                        .join("sfx/menuCancel.wav")
-                        .to_str()
-                        .unwrap(),
-                )
-                .await
-                .unwrap(),
-                menu_select: macroquad::audio::load_sound(
-                    base_assets_path
-                        .join("sfx/menuSelect.wav")
-                        .to_str()
-                        .unwrap(),
-                )
-                .await
-                .unwrap(),
-                menu_move: macroquad::audio::load_sound(
-                    base_assets_path.join("sfx/menuMove.wav").to_str().unwrap(),
-                )
-                .await
-                .unwrap(),
+            variants: vec![silence.clone(); PITCHES.len() * PANS.len()],
+            durations: vec![0.; PITCHES.len()],
+            voices: Voices::new(1),
+            rng: Rng::new(0),
+        }
+    }
+
+    /// plays a random pitch of the sound panned to `x` on the virtual screen, unless as many
+    /// copies as allowed are still playing
+    pub fn play(&mut self, settings: &Settings, x: f32) {
+        let volume = settings.gain(Volume::Sfx) * (1. - self.rng.ratio() * VOLUME_VARIATION);
+        if volume <= 0. {
+            return;
+        }
+
+        let pitch = (self.rng.ratio() * PITCHES.len() as f32) as usize;
+        if !self.voices.start(get_time(), self.durations[pitch]) {
+            return;
+        }
+
+        macroquad::audio::play_sound(
+            &self.variants[pitch * PANS.len() + pan_index(x)],
+            PlaySoundParams {
+                looped: false,
+                volume,
             },
+        );
+    }
+}
+
+/// reads the sound with the id from the asset manifest, rendering its variants if it's varied
+pub async fn load(
+    id: &str,
+    base_assets_path: &Path,
+    entry: &SoundEntry,
+) -> Result<LoadedSound, AssetError> {
+    let path = base_assets_path.join(&entry.path);
+    match (entry.looping, entry.varied) {
+        (true, true) => Err(AssetError::Decode {
+            id: id.to_string(),
+            path,
+            reason: "can't be both looping and varied".to_string(),
+        }),
+        (false, true) => Ok(LoadedSound::Varied(
+            VariedSfx::load(id, &path, entry.max_voices).await?,
+        )),
+        (true, false) => Ok(LoadedSound::Music(load_sound(id, &path).await?)),
+        (false, false) => Ok(LoadedSound::Sfx(load_sound(id, &path).await?)),
+    }
+}
+
+/// reads a sound file, checking it's a format Macroquad can play first as it panics on ones it
+/// can't
+async fn load_sound(id: &str, path: &Path) -> Result<Sound, AssetError> {
+    let bytes = read_asset(id, path).await?;
+    check_sound_format(&bytes).map_err(|reason| AssetError::Decode {
+        id: id.to_string(),
+        path: path.to_path_buf(),
+        reason,
+    })?;
+    Ok(load_sound_from_bytes(&bytes).await.unwrap())
+}
+
+/// the WAV format tag for 32-bit float samples
+const WAV_FORMAT_FLOAT: u16 = 3;
+/// the WAV format tag for a format given by a GUID later in the fmt chunk, usually PCM
+const WAV_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// whether the file is a WAV or Ogg Vorbis file Macroquad can play, from its headers; it can't
+/// play other formats, or more than 2 channels, and panics on them rather than erroring
+pub fn check_sound_format(bytes: &[u8]) -> Result<(), String> {
+    let channels = if bytes.starts_with(b"RIFF") {
+        let (format, _) = read_wav(bytes)?;
+        let supported = match format.tag {
+            WAV_FORMAT_PCM | WAV_FORMAT_EXTENSIBLE => {
+                matches!(format.bits_per_sample, 8 | 16 | 24 | 32)
+            }
+            WAV_FORMAT_FLOAT => format.bits_per_sample == 32,
+            _ => false,
+        };
+        if !supported || format.sample_rate == 0 {
+            return Err(format!(
+                "unsupported WAV format {} with {} bits at {} Hz",
+                format.tag, format.bits_per_sample, format.sample_rate
+            ));
         }
+        format.channels
+    } else if bytes.starts_with(b"OggS") {
+        vorbis_channels(bytes).ok_or("not an Ogg Vorbis file")? as u16
+    } else {
+        return Err("not a WAV or Ogg Vorbis file".to_string());
+    };
+
+    if !(1..=2).contains(&channels) {
+        return Err(format!(
+            "only mono and stereo are supported, got {channels} channels"
+        ));
+    }
+    Ok(())
+}
+
+/// the channel count from an Ogg file's first page, if it starts with a Vorbis identification
+/// header
+fn vorbis_channels(bytes: &[u8]) -> Option<u8> {
+    // the page header is 27 bytes, then a byte per segment, then the first packet
+    let segments = *bytes.get(26)? as usize;
+    let packet = bytes.get(27 + segments..)?;
+    // packet type 1, "vorbis", a 4 byte version, then the channel count
+    if !packet.starts_with(b"\x01vorbis") {
+        return None;
     }
+    packet.get(11).copied()
 }
 
-pub fn play_sfx(ctx: &Context, sfx: &Sound) {
-    if ctx.settings.is_muted() {
+/// a moment of nothing, played in place of sounds that couldn't be loaded
+async fn silence() -> Sound {
+    let pcm = Pcm {
+        sample_rate: 22050,
+        channels: 1,
+        samples: vec![0.; 64],
+    };
+    load_sound_from_bytes(&encode_wav(&pcm)).await.unwrap()
+}
+
+/// the variant in `PANS` closest to where `x` is across the virtual screen
+fn pan_index(x: f32) -> usize {
+    let ratio = (x / VIRTUAL_WIDTH).clamp(0., 1.);
+    (ratio * (PANS.len() - 1) as f32).round() as usize
+}
+
+/// plays the sound with the id from the asset manifest once on the sfx bus, as loud as the
+/// volume settings allow
+pub fn play_sfx(ctx: &Context, id: &str) {
+    let sfx = ctx.audio.sfx(id);
+    let volume = ctx.settings.gain(Volume::Sfx);
+    if volume <= 0. {
         return;
     }
 
-    macroquad::audio::play_sound_once(sfx);
+    macroquad::audio::play_sound(
+        sfx,
+        PlaySoundParams {
+            looped: false,
+            volume,
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pan_index() {
+        assert_eq!(pan_index(0.), 0);
+        assert_eq!(pan_index(VIRTUAL_WIDTH / 2.), PANS.len() / 2);
+        assert_eq!(pan_index(VIRTUAL_WIDTH), PANS.len() - 1);
+        // off screen is as far to the side as it goes
+        assert_eq!(pan_index(-100.), 0);
+        assert_eq!(pan_index(VIRTUAL_WIDTH * 2.), PANS.len() - 1);
+    }
+
+    #[test]
+    fn test_check_sound_format() {
+        let wav = encode_wav(&Pcm {
+            sample_rate: 100,
+            channels: 1,
+            samples: vec![0.; 10],
+        });
+        assert!(check_sound_format(&wav).is_ok());
+        assert!(check_sound_format(b"").is_err());
+        assert!(check_sound_format(b"<html>404 Not Found</html>").is_err());
+        // Macroquad isn't built with FLAC support
+        assert!(check_sound_format(b"fLaC\0\0\0\x22").is_err());
+    }
+
+    #[test]
+    fn test_wav_channels_are_checked() {
+        let wav = |channels| {
+            encode_wav(&Pcm {
+                sample_rate: 100,
+                channels,
+                samples: vec![0.; 12],
+            })
+        };
+        assert!(check_sound_format(&wav(2)).is_ok());
+        assert!(check_sound_format(&wav(3)).is_err());
+        assert!(check_sound_format(&wav(0)).is_err());
+
+        // a broken fmt chunk that says it's longer than the file
+        let mut truncated = wav(1);
+        truncated[16] = 0xFF;
+        assert!(check_sound_format(&truncated).is_err());
+    }
+
+    #[test]
+    fn test_ogg_channels_are_checked() {
+        let ogg = |channels| {
+            let mut bytes = b"OggS".to_vec();
+            bytes.resize(26, 0);
+            // one segment, holding the identification packet
+            bytes.extend([1, 30]);
+            bytes.extend(b"\x01vorbis");
+            bytes.extend([0; 4]);
+            bytes.push(channels);
+            bytes.resize(27 + 1 + 30, 0);
+            bytes
+        };
+        assert!(check_sound_format(&ogg(1)).is_ok());
+        assert!(check_sound_format(&ogg(6)).is_err());
+        assert!(check_sound_format(b"OggS").is_err());
+    }
 }
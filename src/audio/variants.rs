@@ -0,0 +1,252 @@
+/// audio samples from -1 to 1, interleaved by channel
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pcm {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub samples: Vec<f32>,
+}
+
+/// the WAV format tag for uncompressed PCM
+pub const WAV_FORMAT_PCM: u16 = 1;
+
+/// how a WAV file's samples are stored, from its fmt chunk
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WavFormat {
+    /// the format tag, e.g. `WAV_FORMAT_PCM`
+    pub tag: u16,
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub bits_per_sample: u16,
+}
+
+/// reads a 16-bit PCM WAV file with one or two channels
+pub fn decode_wav(bytes: &[u8]) -> Result<Pcm, String> {
+    let (format, data) = read_wav(bytes)?;
+    if format.tag != WAV_FORMAT_PCM || format.bits_per_sample != 16 {
+        return Err(format!(
+            "only 16-bit PCM is supported, got format {} with {} bits",
+            format.tag, format.bits_per_sample
+        ));
+    }
+    // the variants are played through the same decoder as everything else, which can't resample
+    // from 0 Hz or mix more than two channels
+    if !(1..=2).contains(&format.channels) {
+        return Err(format!(
+            "only mono and stereo are supported, got {} channels",
+            format.channels
+        ));
+    }
+    if format.sample_rate == 0 {
+        return Err("the sample rate is 0 Hz".to_string());
+    }
+
+    let samples = data
+        .chunks_exact(2)
+        .map(|s| i16::from_le_bytes([s[0], s[1]]) as f32 / i16::MAX as f32)
+        .collect();
+    Ok(Pcm {
+        sample_rate: format.sample_rate,
+        channels: format.channels,
+        samples,
+    })
+}
+
+/// walks a WAV file's chunks for its format and the bytes of its samples
+pub fn read_wav(bytes: &[u8]) -> Result<(WavFormat, &[u8]), String> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err("not a WAV file".to_string());
+    }
+
+    let u16_at = |at: usize| u16::from_le_bytes([bytes[at], bytes[at + 1]]);
+    let u32_at =
+        |at: usize| u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]);
+
+    let mut format = None;
+    let mut data = None;
+    let mut at = 12;
+    while at + 8 <= bytes.len() {
+        let id = &bytes[at..at + 4];
+        let len = u32_at(at + 4) as usize;
+        let body = at + 8;
+        if body + len > bytes.len() {
+            return Err("truncated chunk".to_string());
+        }
+        match id {
+            b"fmt " if len >= 16 => {
+                format = Some(WavFormat {
+                    tag: u16_at(body),
+                    channels: u16_at(body + 2),
+                    sample_rate: u32_at(body + 4),
+                    bits_per_sample: u16_at(body + 14),
+                })
+            }
+            b"data" => data = Some(&bytes[body..body + len]),
+            _ => {}
+        }
+        // chunks are padded to an even length
+        at = body + len + len % 2;
+    }
+
+    let format = format.ok_or("missing fmt chunk")?;
+    let data = data.ok_or("missing data chunk")?;
+    Ok((format, data))
+}
+
+/// writes the audio as a 16-bit PCM WAV file
+pub fn encode_wav(pcm: &Pcm) -> Vec<u8> {
+    let block_align = pcm.channels * 2;
+    let data_len = pcm.samples.len() as u32 * 2;
+
+    let mut bytes = Vec::with_capacity(44 + data_len as usize);
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+    bytes.extend_from_slice(b"WAVE");
+    bytes.extend_from_slice(b"fmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&WAV_FORMAT_PCM.to_le_bytes());
+    bytes.extend_from_slice(&pcm.channels.to_le_bytes());
+    bytes.extend_from_slice(&pcm.sample_rate.to_le_bytes());
+    bytes.extend_from_slice(&(pcm.sample_rate * block_align as u32).to_le_bytes());
+    bytes.extend_from_slice(&block_align.to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_len.to_le_bytes());
+    for sample in &pcm.samples {
+        let sample = (sample.clamp(-1., 1.) * i16::MAX as f32) as i16;
+        bytes.extend_from_slice(&sample.to_le_bytes());
+    }
+    bytes
+}
+
+impl Pcm {
+    /// how long it plays for, in seconds
+    pub fn duration(&self) -> f32 {
+        self.samples.len() as f32 / self.channels as f32 / self.sample_rate as f32
+    }
+
+    /// a stereo copy played `pitch` times faster (and higher), placed at `pan`, from -1 (left)
+    /// to 1 (right)
+    pub fn variant(&self, pitch: f32, pan: f32) -> Pcm {
+        let channels = self.channels as usize;
+        let mono: Vec<f32> = self
+            .samples
+            .chunks_exact(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect();
+
+        // constant power panning, so it's as loud in the middle as at the sides
+        let angle = (pan.clamp(-1., 1.) + 1.) * std::f32::consts::FRAC_PI_4;
+        let (left, right) = (angle.cos(), angle.sin());
+
+        let len = (mono.len() as f32 / pitch) as usize;
+        let mut samples = Vec::with_capacity(len * 2);
+        for i in 0..len {
+            // linear interpolation between the two nearest source samples
+            let position = i as f32 * pitch;
+            let index = position as usize;
+            let a = mono[index];
+            let b = mono.get(index + 1).copied().unwrap_or(0.);
+            let sample = a + (b - a) * position.fract();
+            samples.push(sample * left);
+            samples.push(sample * right);
+        }
+
+        Pcm {
+            sample_rate: self.sample_rate,
+            channels: 2,
+            samples,
+        }
+    }
+}
+
+/// keeps track of how many copies of a sound are playing, to cap them
+#[derive(Clone, Debug)]
+pub struct Voices {
+    max: usize,
+    /// when each playing copy finishes, in seconds
+    ends: Vec<f64>,
+}
+
+impl Voices {
+    pub fn new(max: usize) -> Self {
+        Self { max, ends: vec![] }
+    }
+
+    /// takes a voice for a copy lasting `duration` seconds starting at `now`, if one is free
+    pub fn start(&mut self, now: f64, duration: f32) -> bool {
+        self.ends.retain(|end| *end > now);
+        if self.ends.len() >= self.max {
+            return false;
+        }
+        self.ends.push(now + duration as f64);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ramp() -> Pcm {
+        Pcm {
+            sample_rate: 100,
+            channels: 1,
+            samples: (0..100).map(|i| i as f32 / 100.).collect(),
+        }
+    }
+
+    #[test]
+    fn test_wav_round_trip() {
+        let pcm = ramp();
+        let decoded = decode_wav(&encode_wav(&pcm)).unwrap();
+        assert_eq!(decoded.sample_rate, 100);
+        assert_eq!(decoded.channels, 1);
+        for (a, b) in pcm.samples.iter().zip(&decoded.samples) {
+            assert!((a - b).abs() < 1e-4);
+        }
+        assert!(decode_wav(b"RIFF").is_err());
+
+        let zero_rate = Pcm {
+            sample_rate: 0,
+            ..ramp()
+        };
+        assert!(decode_wav(&encode_wav(&zero_rate)).is_err());
+        let surround = Pcm {
+            channels: 3,
+            samples: vec![0.; 300],
+            ..ramp()
+        };
+        assert!(decode_wav(&encode_wav(&surround)).is_err());
+    }
+
+    #[test]
+    fn test_pitch_changes_duration() {
+        let pcm = ramp();
+        assert_eq!(pcm.duration(), 1.);
+        assert_eq!(pcm.variant(1., 0.).duration(), 1.);
+        assert_eq!(pcm.variant(2., 0.).duration(), 0.5);
+        assert_eq!(pcm.variant(0.5, 0.).duration(), 2.);
+    }
+
+    #[test]
+    fn test_pan() {
+        let pcm = ramp();
+        let left = pcm.variant(1., -1.);
+        let right = pcm.variant(1., 1.);
+        let center = pcm.variant(1., 0.);
+        // sample 50 of each channel
+        assert!(left.samples[100].abs() > 0.49 && left.samples[101].abs() < 1e-6);
+        assert!(right.samples[100].abs() < 1e-6 && right.samples[101].abs() > 0.49);
+        assert!((center.samples[100] - center.samples[101]).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_voice_limit() {
+        let mut voices = Voices::new(2);
+        assert!(voices.start(0., 1.));
+        assert!(voices.start(0.1, 1.));
+        assert!(!voices.start(0.2, 1.));
+        // the first one has finished
+        assert!(voices.start(1.05, 1.));
+    }
+}
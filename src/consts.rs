@@ -4,5 +4,10 @@ pub const VIRTUAL_HEIGHT: f32 = 720.0;
 pub const TITLE_Y_INSET: f32 = 120.0;
 /// how far right to display text when aligned
 pub const X_INSET: f32 = 200.;
+/// seconds of simulation advanced by each fixed update, independent of the display's frame rate
+pub const FIXED_TIMESTEP: f32 = 1. / 120.;
+/// longest frame time the simulation will try to catch up on, so a long hitch (e.g. dragging the
+/// window) doesn't snowball into more and more fixed updates per frame
+pub const MAX_FRAME_TIME: f32 = 0.25;
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 pub const PKG_NAME: &str = env!("CARGO_PKG_NAME");
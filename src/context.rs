@@ -1,7 +1,11 @@
-use crate::assets_path::determine_asset_path;
+use crate::asset_error::AssetError;
+use crate::assets_path::{determine_asset_path, ASSETS_DIR};
 use crate::audio;
 use crate::consts::*;
 use crate::font;
+use crate::input::InputState;
+use crate::level::Level;
+use crate::music::MusicPlayer;
 use crate::save::Save;
 use crate::scene::EScene;
 use crate::settings::Settings;
@@ -11,22 +15,39 @@ use macroquad::math::Rect;
 use macroquad::miniquad::FilterMode;
 use macroquad::texture::render_target;
 use macroquad::{camera::Camera2D, texture::RenderTarget};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
 
 /// game-wide data and resources
 pub struct Context {
     pub request_quit: bool,
     pub gamepads: Gamepads,
+    /// what the player is pressing this frame, read once at the start of it
+    pub input: InputState,
     pub textures: texture::TextureAtlas,
     pub fonts: font::FontAtlas,
     pub audio: audio::AudioAtlas,
+    pub music: MusicPlayer,
     pub render_target: RenderTarget,
     pub render_target_cam: Camera2D,
     pub switch_scene_to: Option<EScene>,
     pub settings: Settings,
     pub save: Save,
+    /// every level in the game, in the order they're played, empty until they've been loaded
+    pub levels: Vec<Level>,
+    /// where the assets were loaded from
+    pub assets_path: PathBuf,
+    /// ids of the levels whose own assets have been loaded
+    pub loaded_level_assets: BTreeSet<String>,
+    /// assets that couldn't be loaded and were replaced by placeholders, until they've been shown
+    pub asset_errors: Vec<AssetError>,
+    /// how far the current frame is between the last fixed update and the next one, from 0 to 1,
+    /// for drawing moving things smoothly in between
+    pub interpolation: f32,
 }
 
 impl Context {
+    /// without any assets, they're added by a `Loader`
     pub async fn default() -> Self {
         let render_target = render_target(VIRTUAL_WIDTH as u32, VIRTUAL_HEIGHT as u32);
         render_target.texture.set_filter(FilterMode::Nearest);
@@ -36,19 +57,31 @@ impl Context {
             Camera2D::from_display_rect(Rect::new(0., 0., VIRTUAL_WIDTH, VIRTUAL_HEIGHT));
         render_target_cam.render_target = Some(render_target.clone());
 
-        let base_assets_path = determine_asset_path();
+        let mut asset_errors = vec![];
+        // carry on with the working dir's assets, in case they're there after all
+        let base_assets_path = determine_asset_path().unwrap_or_else(|e| {
+            asset_errors.push(e);
+            PathBuf::from(ASSETS_DIR)
+        });
 
         Self {
             gamepads: Gamepads::new(),
+            input: InputState::default(),
             request_quit: false,
-            textures: texture::TextureAtlas::new(&base_assets_path).await,
-            audio: audio::AudioAtlas::new(&base_assets_path).await,
-            fonts: font::FontAtlas::new(&base_assets_path).await,
+            textures: texture::TextureAtlas::default(),
+            audio: audio::AudioAtlas::new().await,
+            music: MusicPlayer::default(),
+            fonts: font::FontAtlas::default(),
             render_target,
             render_target_cam,
             switch_scene_to: None,
             settings: Settings::load(),
             save: Save::load(),
+            levels: vec![],
+            assets_path: base_assets_path,
+            loaded_level_assets: BTreeSet::new(),
+            asset_errors,
+            interpolation: 0.,
         }
     }
 }
@@ -1,22 +1,45 @@
+use std::collections::BTreeMap;
 use std::path::Path;
 
-use macroquad::text::{load_ttf_font, Font};
+use macroquad::text::{load_ttf_font_from_bytes, Font};
 
+use crate::asset_error::{read_asset, AssetError};
+use crate::manifest::FontEntry;
+
+/// id of the font text is drawn with
+pub const REGULAR: &str = "regular";
+
+/// every font in the asset manifest, by id
+#[derive(Default)]
 pub struct FontAtlas {
-    pub regular: Font,
+    fonts: BTreeMap<String, Font>,
 }
 
 impl FontAtlas {
-    pub async fn new(base_assets_path: &Path) -> Self {
-        let mut regular = load_ttf_font(
-            base_assets_path
-                .join("Atkinson-Hyperlegible-Regular-102.ttf")
-                .to_str()
-                .unwrap(),
-        )
-        .await
-        .unwrap();
-        regular.set_filter(macroquad::miniquad::FilterMode::Linear);
-        Self { regular }
+    pub fn insert(&mut self, id: String, font: Font) {
+        self.fonts.insert(id, font);
+    }
+
+    /// the font with the id from the asset manifest, `None` for Macroquad's built-in font if it
+    /// couldn't be loaded
+    pub fn get(&self, id: &str) -> Option<&Font> {
+        self.fonts.get(id)
     }
 }
+
+/// reads the font with the id from the asset manifest
+pub async fn load(
+    id: &str,
+    base_assets_path: &Path,
+    entry: &FontEntry,
+) -> Result<Font, AssetError> {
+    let path = base_assets_path.join(&entry.path);
+    let bytes = read_asset(id, &path).await?;
+    let mut font = load_ttf_font_from_bytes(&bytes).map_err(|e| AssetError::Decode {
+        id: id.to_string(),
+        path,
+        reason: e.to_string(),
+    })?;
+    font.set_filter(entry.filter.into());
+    Ok(font)
+}
@@ -1,7 +1,20 @@
 use gamepads::Gamepads;
-use macroquad::input::KeyCode;
-use macroquad::input::{is_key_down, is_key_pressed};
+use macroquad::input::{
+    is_key_down, is_key_pressed, is_mouse_button_down, is_mouse_button_pressed, mouse_position,
+    touches, MouseButton, TouchPhase,
+};
+use macroquad::math::Vec2;
+use serde::{Deserialize, Serialize};
 
+use crate::letterbox::Letterbox;
+use crate::settings::Settings;
+
+pub mod bindings;
+pub mod touch;
+
+use bindings::Bindings;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
 pub enum Action {
     /// move up (player, menu, etc.)
     Up,
@@ -15,114 +28,432 @@ pub enum Action {
     Confirm,
     /// go back in the menu
     Cancel,
-    /// reset the level to the starting positions
-    Reset,
-    /// go back a move
-    Rewind,
+    /// shoot, repeatedly while held
+    Fire,
     /// the gameplay and bring up a menu
     Pause,
 }
 
-/// just pressed, not held down
-pub fn action_pressed(action: Action, gamepads: &Gamepads) -> bool {
-    keyboard_pressed(&action) || gamepad_pressed(&action, gamepads)
+const ACTION_COUNT: usize = 8;
+
+impl Action {
+    /// every action, in the order they're listed on the controls page
+    pub const ALL: [Action; ACTION_COUNT] = [
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+        Action::Confirm,
+        Action::Cancel,
+        Action::Fire,
+        Action::Pause,
+    ];
+
+    /// which way the action points, for the ones the stick can trigger
+    fn direction(self) -> Option<Vec2> {
+        match self {
+            Action::Up => Some(Vec2::NEG_Y),
+            Action::Down => Some(Vec2::Y),
+            Action::Left => Some(Vec2::NEG_X),
+            Action::Right => Some(Vec2::X),
+            _ => None,
+        }
+    }
 }
 
-/// held down for multiple frames
-pub fn action_down(action: Action, gamepads: &Gamepads) -> bool {
-    keyboard_down(&action) || gamepad_down(&action, gamepads)
+/// how far the stick has to be pushed in a direction to count as holding that direction's action
+const STICK_ACTION_THRESHOLD: f32 = 0.5;
+/// seconds an action has to be held before it starts repeating, for scrolling through menus
+const REPEAT_DELAY: f32 = 0.4;
+/// seconds between repeats once an action is repeating
+const REPEAT_INTERVAL: f32 = 0.1;
+
+/// what the devices reported for a single frame, before being turned into an `InputState`
+///
+/// Tests can make these up instead of reading them from the keyboard and gamepads.
+#[derive(Clone, Debug, Default)]
+pub struct FrameInput {
+    /// which actions have a key or button bound to them held down, indexed like `Action::ALL`
+    pub down: [bool; ACTION_COUNT],
+    /// the left stick, with the deadzone applied, y pointing down
+    pub stick: Vec2,
+    /// where the mouse or first touch is, in virtual screen coordinates
+    pub pointer: Option<Vec2>,
+    /// whether the pointer is clicking or touching
+    pub pointer_down: bool,
+    /// ids and virtual screen positions of the fingers on the screen
+    pub touches: Vec<(u64, Vec2)>,
 }
 
-fn keyboard_pressed(action: &Action) -> bool {
-    match action {
-        Action::Up => is_key_pressed(KeyCode::W) || is_key_pressed(KeyCode::Up),
-        Action::Down => is_key_pressed(KeyCode::S) || is_key_pressed(KeyCode::Down),
-        Action::Left => is_key_pressed(KeyCode::A) || is_key_pressed(KeyCode::Left),
-        Action::Right => is_key_pressed(KeyCode::D) || is_key_pressed(KeyCode::Right),
-        Action::Rewind => is_key_pressed(KeyCode::K) || is_key_pressed(KeyCode::X),
-        Action::Reset => is_key_pressed(KeyCode::L) || is_key_pressed(KeyCode::C),
-        Action::Confirm => is_key_pressed(KeyCode::J) || is_key_pressed(KeyCode::Z),
-        Action::Cancel => is_key_pressed(KeyCode::K) || is_key_pressed(KeyCode::X),
-        Action::Pause => is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::P),
+impl FrameInput {
+    /// reads the keyboard and every connected gamepad through the player's bindings
+    pub fn read(gamepads: &Gamepads, settings: &Settings) -> Self {
+        let bindings = settings.bindings();
+        let mut down = [false; ACTION_COUNT];
+        for (i, action) in Action::ALL.into_iter().enumerate() {
+            down[i] = keyboard_down(action, bindings) || gamepad_down(action, bindings, gamepads);
+        }
+
+        // the gamepads crate has y pointing up, the screen has it pointing down
+        let raw_stick = gamepads
+            .all()
+            .map(|g| Vec2::new(g.left_stick_x(), -g.left_stick_y()))
+            .max_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))
+            .unwrap_or_default();
+        let stick = apply_deadzone(
+            raw_stick,
+            settings.stick_deadzone(),
+            settings.is_stick_snap(),
+        );
+
+        let letterbox = Letterbox::current();
+        let touches: Vec<(u64, Vec2)> = touches()
+            .into_iter()
+            .filter(|touch| !matches!(touch.phase, TouchPhase::Ended | TouchPhase::Cancelled))
+            .map(|touch| (touch.id, letterbox.to_virtual(touch.position)))
+            .collect();
+
+        // a touch also works like a click, for tapping menu items
+        let pointer = match touches.first() {
+            Some((_, position)) => *position,
+            None => letterbox.to_virtual(mouse_position().into()),
+        };
+        let pointer_down = !touches.is_empty()
+            || is_mouse_button_down(MouseButton::Left)
+            || is_mouse_button_pressed(MouseButton::Left);
+
+        Self {
+            down,
+            stick,
+            pointer: Some(pointer),
+            pointer_down,
+            touches,
+        }
     }
 }
 
-fn keyboard_down(action: &Action) -> bool {
-    match action {
-        Action::Up => is_key_down(KeyCode::W) || is_key_down(KeyCode::Up),
-        Action::Down => is_key_down(KeyCode::S) || is_key_down(KeyCode::Down),
-        Action::Left => is_key_down(KeyCode::A) || is_key_down(KeyCode::Left),
-        Action::Right => is_key_down(KeyCode::D) || is_key_down(KeyCode::Right),
-        Action::Reset => is_key_down(KeyCode::L) || is_key_down(KeyCode::C),
-        Action::Rewind => is_key_down(KeyCode::K) || is_key_down(KeyCode::X),
-        Action::Confirm => is_key_down(KeyCode::J) || is_key_down(KeyCode::Z),
-        Action::Cancel => is_key_down(KeyCode::K) || is_key_down(KeyCode::X),
-        Action::Pause => is_key_down(KeyCode::Escape) || is_key_down(KeyCode::P),
+/// the state of every action for the current frame, built once per frame in the main loop
+#[derive(Clone, Debug, Default)]
+pub struct InputState {
+    /// seconds each action has been held down for, or `None` if it isn't
+    held_for: [Option<f32>; ACTION_COUNT],
+    pressed: [bool; ACTION_COUNT],
+    released: [bool; ACTION_COUNT],
+    /// which way and how far the player wants to move, up to a length of 1
+    movement: Vec2,
+    stick: Vec2,
+    pointer: Option<Vec2>,
+    pointer_moved: bool,
+    pointer_down: bool,
+    pointer_pressed: bool,
+    touches: Vec<(u64, Vec2)>,
+    /// seconds since the last frame
+    dt: f32,
+}
+
+impl InputState {
+    /// moves on to the next frame, `dt` seconds after the last one
+    ///
+    /// Directions count as held while the stick is pushed far enough that way, so flicking it
+    /// presses them in menus.
+    pub fn advance(&mut self, frame: FrameInput, dt: f32) {
+        for (i, action) in Action::ALL.into_iter().enumerate() {
+            let stick_down = action
+                .direction()
+                .is_some_and(|direction| frame.stick.dot(direction) >= STICK_ACTION_THRESHOLD);
+            let down = frame.down[i] || stick_down;
+            let was_down = self.held_for[i].is_some();
+
+            self.pressed[i] = down && !was_down;
+            self.released[i] = !down && was_down;
+            self.held_for[i] = match (down, self.held_for[i]) {
+                (false, _) => None,
+                (true, None) => Some(0.),
+                (true, Some(held_for)) => Some(held_for + dt),
+            };
+        }
+
+        // keys and D-pad buttons move at full speed, while the stick moves as fast as it's pushed
+        let digital: Vec2 = Action::ALL
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| frame.down[*i])
+            .filter_map(|(_, action)| action.direction())
+            .sum();
+        self.movement = if digital != Vec2::ZERO {
+            digital.normalize()
+        } else {
+            frame.stick
+        };
+        self.stick = frame.stick;
+
+        self.pointer_moved = frame.pointer.is_some() && frame.pointer != self.pointer;
+        self.pointer = frame.pointer;
+        self.pointer_pressed = frame.pointer_down && !self.pointer_down;
+        self.pointer_down = frame.pointer_down;
+        self.touches = frame.touches;
+        self.dt = dt;
+    }
+
+    /// just pressed this frame, not held down
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed[action as usize]
+    }
+
+    /// held down, including the frame it was pressed on
+    pub fn down(&self, action: Action) -> bool {
+        self.held_for[action as usize].is_some()
+    }
+
+    /// just let go of this frame
+    pub fn released(&self, action: Action) -> bool {
+        self.released[action as usize]
+    }
+
+    /// just pressed, or held long enough to repeat this frame, like keys do when typing
+    pub fn repeated(&self, action: Action) -> bool {
+        // how many times the action has fired after being held for `held_for` seconds
+        let repeats = |held_for: f32| {
+            if held_for < REPEAT_DELAY {
+                0
+            } else {
+                ((held_for - REPEAT_DELAY) / REPEAT_INTERVAL) as u32 + 1
+            }
+        };
+        match self.held_for[action as usize] {
+            Some(held_for) => {
+                self.pressed(action) || repeats(held_for) > repeats(held_for - self.dt)
+            }
+            None => false,
+        }
+    }
+
+    /// seconds the action has been held down for, 0 if it isn't or was just pressed
+    pub fn held_duration(&self, action: Action) -> f32 {
+        self.held_for[action as usize].unwrap_or(0.)
+    }
+
+    /// which way and how far the player wants to move, up to a length of 1
+    pub fn movement(&self) -> Vec2 {
+        self.movement
+    }
+
+    /// the left stick, with the deadzone applied, y pointing down
+    pub fn stick(&self) -> Vec2 {
+        self.stick
+    }
+
+    /// where the pointer is, in virtual screen coordinates
+    pub fn pointer(&self) -> Option<Vec2> {
+        self.pointer
+    }
+
+    /// whether the pointer moved since the last frame, so it doesn't take over from the keys
+    /// while sitting still
+    pub fn pointer_moved(&self) -> bool {
+        self.pointer_moved
+    }
+
+    /// just clicked this frame, not held down
+    pub fn pointer_pressed(&self) -> bool {
+        self.pointer_pressed
+    }
+
+    /// ids and virtual screen positions of the fingers on the screen
+    pub fn touches(&self) -> &[(u64, Vec2)] {
+        &self.touches
     }
 }
 
-/// checks the action for any of the connected gamepads
-fn gamepad_pressed(action: &Action, gamepads: &Gamepads) -> bool {
-    match action {
-        Action::Up => gamepads
-            .all()
-            .any(|g| g.is_just_pressed(gamepads::Button::DPadUp)),
-        Action::Down => gamepads
-            .all()
-            .any(|g| g.is_just_pressed(gamepads::Button::DPadDown)),
-        Action::Left => gamepads
-            .all()
-            .any(|g| g.is_just_pressed(gamepads::Button::DPadLeft)),
-        Action::Right => gamepads
-            .all()
-            .any(|g| g.is_just_pressed(gamepads::Button::DPadRight)),
-        Action::Confirm => gamepads
-            .all()
-            .any(|g| g.is_just_pressed(gamepads::Button::ActionDown)),
-        Action::Cancel => gamepads
-            .all()
-            .any(|g| g.is_just_pressed(gamepads::Button::ActionRight)),
-        Action::Rewind => gamepads
-            .all()
-            .any(|g| g.is_just_pressed(gamepads::Button::ActionLeft)),
-        Action::Reset => gamepads
-            .all()
-            .any(|g| g.is_just_pressed(gamepads::Button::ActionUp)),
-        Action::Pause => gamepads
-            .all()
-            .any(|g| g.is_just_pressed(gamepads::Button::RightCenterCluster)),
+/// ignores small movements of the stick around its center and rescales the rest to start from 0
+/// at the edge of the deadzone
+///
+/// The deadzone is measured by distance rather than per axis, so diagonals aren't cut off. With
+/// `snap`, the direction is rounded to the nearest of 8 directions, keeping how far it's pushed.
+pub fn apply_deadzone(stick: Vec2, deadzone: f32, snap: bool) -> Vec2 {
+    let length = stick.length();
+    if length <= deadzone {
+        return Vec2::ZERO;
+    }
+
+    let magnitude = ((length - deadzone) / (1. - deadzone)).min(1.);
+    let mut direction = stick / length;
+    if snap {
+        let step = std::f32::consts::FRAC_PI_4;
+        direction = Vec2::from_angle((direction.to_angle() / step).round() * step);
     }
+    direction * magnitude
+}
+
+/// also counts keys that were pressed and let go of within the same frame
+fn keyboard_down(action: Action, bindings: &Bindings) -> bool {
+    bindings
+        .keys(action)
+        .any(|key_code| is_key_down(key_code) || is_key_pressed(key_code))
 }
 
 /// checks the action for any of the connected gamepads
-fn gamepad_down(action: &Action, gamepads: &Gamepads) -> bool {
-    match action {
-        Action::Up => gamepads
-            .all()
-            .any(|g| g.is_currently_pressed(gamepads::Button::DPadUp)),
-        Action::Down => gamepads
-            .all()
-            .any(|g| g.is_currently_pressed(gamepads::Button::DPadDown)),
-        Action::Left => gamepads
-            .all()
-            .any(|g| g.is_currently_pressed(gamepads::Button::DPadLeft)),
-        Action::Right => gamepads
-            .all()
-            .any(|g| g.is_currently_pressed(gamepads::Button::DPadRight)),
-        Action::Confirm => gamepads
-            .all()
-            .any(|g| g.is_currently_pressed(gamepads::Button::ActionDown)),
-        Action::Cancel => gamepads
-            .all()
-            .any(|g| g.is_currently_pressed(gamepads::Button::ActionRight)),
-        Action::Rewind => gamepads
-            .all()
-            .any(|g| g.is_currently_pressed(gamepads::Button::ActionLeft)),
-        Action::Reset => gamepads
+fn gamepad_down(action: Action, bindings: &Bindings, gamepads: &Gamepads) -> bool {
+    bindings.buttons(action).any(|button| {
+        gamepads
             .all()
-            .any(|g| g.is_currently_pressed(gamepads::Button::ActionUp)),
-        Action::Pause => gamepads
-            .all()
-            .any(|g| g.is_currently_pressed(gamepads::Button::RightCenterCluster)),
+            .any(|g| g.is_currently_pressed(button) || g.is_just_pressed(button))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f32 = 1. / 60.;
+
+    fn frame(down: &[Action], stick: Vec2) -> FrameInput {
+        let mut frame = FrameInput {
+            stick,
+            ..Default::default()
+        };
+        for action in down {
+            frame.down[*action as usize] = true;
+        }
+        frame
+    }
+
+    #[test]
+    fn test_press_hold_release() {
+        let mut input = InputState::default();
+        input.advance(frame(&[Action::Confirm], Vec2::ZERO), DT);
+        assert!(input.pressed(Action::Confirm));
+        assert!(input.down(Action::Confirm));
+        assert_eq!(input.held_duration(Action::Confirm), 0.);
+
+        for _ in 0..30 {
+            input.advance(frame(&[Action::Confirm], Vec2::ZERO), DT);
+        }
+        assert!(!input.pressed(Action::Confirm));
+        assert!(input.down(Action::Confirm));
+        assert!((input.held_duration(Action::Confirm) - 0.5).abs() < 1e-4);
+
+        input.advance(frame(&[], Vec2::ZERO), DT);
+        assert!(input.released(Action::Confirm));
+        assert!(!input.down(Action::Confirm));
+        assert_eq!(input.held_duration(Action::Confirm), 0.);
+
+        input.advance(frame(&[], Vec2::ZERO), DT);
+        assert!(!input.released(Action::Confirm));
+    }
+
+    #[test]
+    fn test_repeat_after_delay() {
+        let mut input = InputState::default();
+        let mut repeats = vec![];
+        // held for one second
+        for tick in 0..60 {
+            input.advance(frame(&[Action::Down], Vec2::ZERO), DT);
+            if input.repeated(Action::Down) {
+                repeats.push(tick);
+            }
+        }
+        // pressed on the first frame, then after the delay once every interval, at 0.4s, 0.5s, ...
+        // 0.9s give or take a frame of float error
+        assert_eq!(repeats.len(), 1 + 6, "{repeats:?}");
+        assert_eq!(repeats[0], 0);
+        assert!((24..=25).contains(&repeats[1]), "{repeats:?}");
+        assert!(
+            repeats
+                .windows(2)
+                .skip(1)
+                .all(|w| (5..=7).contains(&(w[1] - w[0]))),
+            "{repeats:?}"
+        );
+
+        input.advance(frame(&[], Vec2::ZERO), DT);
+        assert!(!input.repeated(Action::Down));
+    }
+
+    #[test]
+    fn test_pointer() {
+        let mut input = InputState::default();
+        let mut frame = frame(&[], Vec2::ZERO);
+        frame.pointer = Some(Vec2::new(10., 20.));
+        input.advance(frame.clone(), DT);
+        assert!(input.pointer_moved());
+        assert!(!input.pointer_pressed());
+
+        frame.pointer_down = true;
+        input.advance(frame.clone(), DT);
+        assert!(!input.pointer_moved());
+        assert!(input.pointer_pressed());
+
+        input.advance(frame, DT);
+        assert!(!input.pointer_pressed());
+    }
+
+    #[test]
+    fn test_stick_flick_presses_direction() {
+        let mut input = InputState::default();
+        input.advance(frame(&[], Vec2::new(0., -0.3)), DT);
+        assert!(!input.pressed(Action::Up));
+
+        input.advance(frame(&[], Vec2::new(0., -0.9)), DT);
+        assert!(input.pressed(Action::Up));
+        assert!(!input.pressed(Action::Down));
+
+        // held, not pressed again, until it goes back to the center
+        input.advance(frame(&[], Vec2::new(0., -1.)), DT);
+        assert!(!input.pressed(Action::Up));
+        assert!(input.down(Action::Up));
+    }
+
+    #[test]
+    fn test_movement() {
+        let mut input = InputState::default();
+        // partly pushing the stick moves partly
+        input.advance(frame(&[], Vec2::new(0.5, 0.)), DT);
+        assert_eq!(input.movement(), Vec2::new(0.5, 0.));
+
+        // keys move at full speed, even diagonally, and take over from the stick
+        input.advance(frame(&[Action::Up, Action::Left], Vec2::new(0.5, 0.)), DT);
+        let movement = input.movement();
+        assert!((movement.length() - 1.).abs() < 1e-6);
+        assert!(movement.x < 0. && movement.y < 0.);
+    }
+
+    #[test]
+    fn test_deadzone_ignores_small_movements() {
+        assert_eq!(apply_deadzone(Vec2::new(0.1, -0.1), 0.2, false), Vec2::ZERO);
+        assert_eq!(apply_deadzone(Vec2::new(0., 0.2), 0.2, false), Vec2::ZERO);
+    }
+
+    #[test]
+    fn test_deadzone_rescales_magnitude() {
+        let halfway = apply_deadzone(Vec2::new(0.6, 0.), 0.2, false);
+        assert!((halfway.x - 0.5).abs() < 1e-6, "{halfway}");
+
+        let full = apply_deadzone(Vec2::new(0., -1.), 0.2, false);
+        assert!((full - Vec2::NEG_Y).length() < 1e-6, "{full}");
+
+        // some sticks report slightly more than 1 in the corners
+        let corner = apply_deadzone(Vec2::new(1., 1.), 0.2, false);
+        assert!((corner.length() - 1.).abs() < 1e-6, "{corner}");
+    }
+
+    #[test]
+    fn test_deadzone_is_radial() {
+        // each axis alone would be inside the deadzone, but together they're outside of it
+        let diagonal = apply_deadzone(Vec2::new(0.18, 0.18), 0.2, false);
+        assert!(diagonal.x > 0. && diagonal.y > 0.);
+        assert!((diagonal.x - diagonal.y).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_snap_keeps_magnitude() {
+        let stick = Vec2::new(0.7, 0.1);
+        let unsnapped = apply_deadzone(stick, 0.2, false);
+        let snapped = apply_deadzone(stick, 0.2, true);
+        assert!((snapped.length() - unsnapped.length()).abs() < 1e-6);
+        assert!(snapped.y.abs() < 1e-6, "{snapped}");
+
+        let snapped = apply_deadzone(Vec2::new(0.5, 0.6), 0.2, true);
+        assert!((snapped.x - snapped.y).abs() < 1e-6, "{snapped}");
     }
 }
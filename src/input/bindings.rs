@@ -0,0 +1,459 @@
+use std::collections::BTreeMap;
+
+use gamepads::Button;
+use macroquad::input::KeyCode;
+use serde::{Deserialize, Serialize};
+
+use super::Action;
+
+/// which keys and gamepad buttons trigger each action, customizable by the player
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(from = "SavedBindings")]
+pub struct Bindings {
+    keyboard: BTreeMap<Action, Vec<Key>>,
+    gamepad: BTreeMap<Action, Vec<GamepadButton>>,
+}
+
+/// the bindings as saved, which may have been edited by hand to leave actions out or bind the
+/// same key to two of them
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct SavedBindings {
+    keyboard: BTreeMap<Action, Vec<Key>>,
+    gamepad: BTreeMap<Action, Vec<GamepadButton>>,
+}
+
+/// a keyboard key, stored by name since `KeyCode` can't be serialized
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Key(pub KeyCode);
+
+/// a gamepad button, stored by name since `gamepads::Button` can't be serialized
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct GamepadButton(pub Button);
+
+/// every key that can be bound, to look them up by name
+const KEY_CODES: &[KeyCode] = &[
+    KeyCode::Space,
+    KeyCode::Apostrophe,
+    KeyCode::Comma,
+    KeyCode::Minus,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Key0,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Semicolon,
+    KeyCode::Equal,
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::LeftBracket,
+    KeyCode::Backslash,
+    KeyCode::RightBracket,
+    KeyCode::GraveAccent,
+    KeyCode::World1,
+    KeyCode::World2,
+    KeyCode::Escape,
+    KeyCode::Enter,
+    KeyCode::Tab,
+    KeyCode::Backspace,
+    KeyCode::Insert,
+    KeyCode::Delete,
+    KeyCode::Right,
+    KeyCode::Left,
+    KeyCode::Down,
+    KeyCode::Up,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::Home,
+    KeyCode::End,
+    KeyCode::CapsLock,
+    KeyCode::ScrollLock,
+    KeyCode::NumLock,
+    KeyCode::PrintScreen,
+    KeyCode::Pause,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::F13,
+    KeyCode::F14,
+    KeyCode::F15,
+    KeyCode::F16,
+    KeyCode::F17,
+    KeyCode::F18,
+    KeyCode::F19,
+    KeyCode::F20,
+    KeyCode::F21,
+    KeyCode::F22,
+    KeyCode::F23,
+    KeyCode::F24,
+    KeyCode::F25,
+    KeyCode::Kp0,
+    KeyCode::Kp1,
+    KeyCode::Kp2,
+    KeyCode::Kp3,
+    KeyCode::Kp4,
+    KeyCode::Kp5,
+    KeyCode::Kp6,
+    KeyCode::Kp7,
+    KeyCode::Kp8,
+    KeyCode::Kp9,
+    KeyCode::KpDecimal,
+    KeyCode::KpDivide,
+    KeyCode::KpMultiply,
+    KeyCode::KpSubtract,
+    KeyCode::KpAdd,
+    KeyCode::KpEnter,
+    KeyCode::KpEqual,
+    KeyCode::LeftShift,
+    KeyCode::LeftControl,
+    KeyCode::LeftAlt,
+    KeyCode::LeftSuper,
+    KeyCode::RightShift,
+    KeyCode::RightControl,
+    KeyCode::RightAlt,
+    KeyCode::RightSuper,
+    KeyCode::Menu,
+];
+
+impl TryFrom<String> for Key {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        KEY_CODES
+            .iter()
+            .find(|key_code| format!("{key_code:?}") == name)
+            .map(|key_code| Key(*key_code))
+            .ok_or_else(|| format!("unknown key: {name}"))
+    }
+}
+
+impl From<Key> for String {
+    fn from(key: Key) -> Self {
+        format!("{:?}", key.0)
+    }
+}
+
+impl TryFrom<String> for GamepadButton {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        Button::all()
+            .find(|button| format!("{button:?}") == name)
+            .map(GamepadButton)
+            .ok_or_else(|| format!("unknown gamepad button: {name}"))
+    }
+}
+
+impl From<GamepadButton> for String {
+    fn from(button: GamepadButton) -> Self {
+        format!("{:?}", button.0)
+    }
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        let keyboard = [
+            (Action::Up, vec![KeyCode::W, KeyCode::Up]),
+            (Action::Down, vec![KeyCode::S, KeyCode::Down]),
+            (Action::Left, vec![KeyCode::A, KeyCode::Left]),
+            (Action::Right, vec![KeyCode::D, KeyCode::Right]),
+            (Action::Confirm, vec![KeyCode::J, KeyCode::Z]),
+            (Action::Cancel, vec![KeyCode::K, KeyCode::X]),
+            (Action::Fire, vec![KeyCode::Space, KeyCode::L, KeyCode::C]),
+            (Action::Pause, vec![KeyCode::Escape, KeyCode::P]),
+        ];
+        let gamepad = [
+            (Action::Up, vec![Button::DPadUp]),
+            (Action::Down, vec![Button::DPadDown]),
+            (Action::Left, vec![Button::DPadLeft]),
+            (Action::Right, vec![Button::DPadRight]),
+            (Action::Confirm, vec![Button::ActionDown]),
+            (Action::Cancel, vec![Button::ActionRight]),
+            (
+                Action::Fire,
+                vec![Button::FrontRightLower, Button::ActionLeft],
+            ),
+            (Action::Pause, vec![Button::RightCenterCluster]),
+        ];
+
+        Self {
+            keyboard: keyboard
+                .into_iter()
+                .map(|(action, keys)| (action, keys.into_iter().map(Key).collect()))
+                .collect(),
+            gamepad: gamepad
+                .into_iter()
+                .map(|(action, buttons)| (action, buttons.into_iter().map(GamepadButton).collect()))
+                .collect(),
+        }
+    }
+}
+
+impl From<SavedBindings> for Bindings {
+    fn from(saved: SavedBindings) -> Self {
+        let defaults = Bindings::default();
+        Self {
+            keyboard: resolve(saved.keyboard, defaults.keyboard),
+            gamepad: resolve(saved.gamepad, defaults.gamepad),
+        }
+    }
+}
+
+/// keeps each key or button only for the first action it's saved for, then gives every action
+/// left without any its defaults that are still free
+fn resolve<T: Copy + PartialEq>(
+    mut saved: BTreeMap<Action, Vec<T>>,
+    defaults: BTreeMap<Action, Vec<T>>,
+) -> BTreeMap<Action, Vec<T>> {
+    let mut taken = vec![];
+    let mut resolved = BTreeMap::new();
+    for action in Action::ALL {
+        let inputs: Vec<T> = saved
+            .remove(&action)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|input| {
+                let free = !taken.contains(input);
+                if free {
+                    taken.push(*input);
+                }
+                free
+            })
+            .collect();
+        resolved.insert(action, inputs);
+    }
+    for (action, default_inputs) in defaults {
+        let inputs = resolved.entry(action).or_default();
+        if inputs.is_empty() {
+            for input in default_inputs {
+                if !taken.contains(&input) {
+                    taken.push(input);
+                    inputs.push(input);
+                }
+            }
+        }
+    }
+    resolved
+}
+
+impl Bindings {
+    /// the keys bound to the action
+    pub fn keys(&self, action: Action) -> impl Iterator<Item = KeyCode> + '_ {
+        self.keyboard
+            .get(&action)
+            .into_iter()
+            .flatten()
+            .map(|key| key.0)
+    }
+
+    /// the gamepad buttons bound to the action
+    pub fn buttons(&self, action: Action) -> impl Iterator<Item = Button> + '_ {
+        self.gamepad
+            .get(&action)
+            .into_iter()
+            .flatten()
+            .map(|button| button.0)
+    }
+
+    /// makes the key the only one that triggers the action
+    ///
+    /// Errors with the other action the key is already bound to, leaving the bindings as they were
+    pub fn bind_key(&mut self, action: Action, key_code: KeyCode) -> Result<(), Action> {
+        if let Some(other) = Action::ALL
+            .into_iter()
+            .find(|other| *other != action && self.keys(*other).any(|k| k == key_code))
+        {
+            return Err(other);
+        }
+        self.keyboard.insert(action, vec![Key(key_code)]);
+        Ok(())
+    }
+
+    /// makes the button the only one that triggers the action
+    ///
+    /// Errors with the other action the button is already bound to, leaving the bindings as they
+    /// were
+    pub fn bind_button(&mut self, action: Action, button: Button) -> Result<(), Action> {
+        if let Some(other) = Action::ALL
+            .into_iter()
+            .find(|other| *other != action && self.buttons(*other).any(|b| b == button))
+        {
+            return Err(other);
+        }
+        self.gamepad.insert(action, vec![GamepadButton(button)]);
+        Ok(())
+    }
+
+    /// how the action's bindings are shown to the player, e.g. "W, Up / DPadUp"
+    pub fn describe(&self, action: Action) -> String {
+        let keys: Vec<String> = self.keys(action).map(|k| format!("{k:?}")).collect();
+        let buttons: Vec<String> = self.buttons(action).map(|b| format!("{b:?}")).collect();
+        format!("{} / {}", keys.join(", "), buttons.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_names_round_trip() {
+        for key_code in KEY_CODES {
+            let name: String = Key(*key_code).into();
+            assert_eq!(Key::try_from(name).unwrap(), Key(*key_code));
+        }
+        assert!(Key::try_from("NotAKey".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_button_names_round_trip() {
+        for button in Button::all() {
+            let name: String = GamepadButton(button).into();
+            assert_eq!(
+                GamepadButton::try_from(name).unwrap(),
+                GamepadButton(button)
+            );
+        }
+    }
+
+    #[test]
+    fn test_every_action_has_a_default() {
+        let bindings = Bindings::default();
+        for action in Action::ALL {
+            assert!(bindings.keys(action).next().is_some(), "{action:?}");
+            assert!(bindings.buttons(action).next().is_some(), "{action:?}");
+        }
+    }
+
+    #[test]
+    fn test_toml_round_trip() {
+        let mut bindings = Bindings::default();
+        bindings.bind_key(Action::Fire, KeyCode::Enter).unwrap();
+        bindings.bind_button(Action::Pause, Button::Mode).unwrap();
+
+        let toml_str = toml::to_string(&bindings).unwrap();
+        let parsed: Bindings = toml::from_str(&toml_str).unwrap();
+        assert_eq!(parsed, bindings);
+        assert_eq!(
+            parsed.keys(Action::Fire).collect::<Vec<_>>(),
+            [KeyCode::Enter]
+        );
+    }
+
+    #[test]
+    fn test_bound_keys_and_buttons_are_refused() {
+        let mut bindings = Bindings::default();
+        assert_eq!(
+            bindings.bind_key(Action::Fire, KeyCode::Z),
+            Err(Action::Confirm)
+        );
+        assert_eq!(
+            bindings.bind_button(Action::Down, Button::ActionDown),
+            Err(Action::Confirm)
+        );
+        assert_eq!(bindings, Bindings::default());
+
+        // rebinding an action to one of its own keys is fine
+        assert_eq!(bindings.bind_key(Action::Fire, KeyCode::Space), Ok(()));
+        assert_eq!(
+            bindings.keys(Action::Fire).collect::<Vec<_>>(),
+            [KeyCode::Space]
+        );
+        // and its old keys are free again
+        assert_eq!(bindings.bind_key(Action::Pause, KeyCode::C), Ok(()));
+    }
+
+    #[test]
+    fn test_missing_actions_use_defaults() {
+        let parsed: Bindings = toml::from_str("").unwrap();
+        assert_eq!(parsed, Bindings::default());
+
+        let parsed: Bindings = toml::from_str("[keyboard]\nFire = [\"Enter\"]").unwrap();
+        let defaults = Bindings::default();
+        assert_eq!(
+            parsed.keys(Action::Fire).collect::<Vec<_>>(),
+            [KeyCode::Enter]
+        );
+        for action in Action::ALL.into_iter().filter(|a| *a != Action::Fire) {
+            assert!(parsed.keys(action).eq(defaults.keys(action)), "{action:?}");
+        }
+        for action in Action::ALL {
+            assert!(
+                parsed.buttons(action).eq(defaults.buttons(action)),
+                "{action:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_conflicting_saved_bindings_are_dropped() {
+        let parsed: Bindings = toml::from_str(
+            "[keyboard]\nUp = [\"W\", \"Space\"]\nFire = [\"Space\"]\nConfirm = [\"Z\", \"Z\"]",
+        )
+        .unwrap();
+        assert_eq!(
+            parsed.keys(Action::Up).collect::<Vec<_>>(),
+            [KeyCode::W, KeyCode::Space]
+        );
+        assert_eq!(
+            parsed.keys(Action::Confirm).collect::<Vec<_>>(),
+            [KeyCode::Z]
+        );
+        // Fire lost its only key, so it gets its defaults that are still free
+        assert_eq!(
+            parsed.keys(Action::Fire).collect::<Vec<_>>(),
+            [KeyCode::L, KeyCode::C]
+        );
+        for action in Action::ALL {
+            for key in parsed.keys(action) {
+                let bound_to = Action::ALL
+                    .into_iter()
+                    .filter(|other| parsed.keys(*other).any(|k| k == key))
+                    .count();
+                assert_eq!(bound_to, 1, "{key:?}");
+            }
+        }
+    }
+}
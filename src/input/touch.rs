@@ -0,0 +1,203 @@
+use macroquad::color::Color;
+use macroquad::math::Vec2;
+use macroquad::shapes::{draw_circle, draw_circle_lines, draw_rectangle};
+
+use super::apply_deadzone;
+use crate::consts::{VIRTUAL_HEIGHT, VIRTUAL_WIDTH};
+
+/// how far the joystick's knob can be dragged from where the touch started, for full speed
+const JOYSTICK_RADIUS: f32 = 80.;
+/// small wobbles of the thumb don't move the player
+const JOYSTICK_DEADZONE: f32 = 0.15;
+const FIRE_BUTTON_CENTER: Vec2 = Vec2::new(VIRTUAL_WIDTH - 140., VIRTUAL_HEIGHT - 140.);
+const FIRE_BUTTON_RADIUS: f32 = 70.;
+const PAUSE_BUTTON_CENTER: Vec2 = Vec2::new(VIRTUAL_WIDTH - 60., 60.);
+const PAUSE_BUTTON_RADIUS: f32 = 36.;
+const CONTROLS_COLOR: Color = Color::new(1., 1., 1., 0.3);
+const CONTROLS_ACTIVE_COLOR: Color = Color::new(1., 1., 1., 0.6);
+
+/// on-screen controls for touch devices during gameplay: a joystick that appears wherever the
+/// left half of the screen is touched, a fire button and a pause button
+#[derive(Clone, Debug, Default)]
+pub struct TouchControls {
+    /// shown once the screen has been touched, so they stay out of the way with a keyboard
+    pub visible: bool,
+    joystick: Option<Joystick>,
+    /// the touch holding down the fire button
+    fire: Option<u64>,
+    pause_pressed: bool,
+    /// touches from the last frame, to tell which ones just started
+    previous: Vec<u64>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Joystick {
+    touch: u64,
+    /// where the touch started, the center of the joystick
+    origin: Vec2,
+    position: Vec2,
+}
+
+impl TouchControls {
+    /// follows the touches, given as ids and positions in virtual screen coordinates
+    pub fn update(&mut self, touches: &[(u64, Vec2)]) {
+        if !touches.is_empty() {
+            self.visible = true;
+        }
+
+        let position = |id: u64| touches.iter().find(|(t, _)| *t == id).map(|(_, p)| *p);
+        self.joystick = self.joystick.and_then(|joystick| {
+            position(joystick.touch).map(|position| Joystick {
+                position,
+                ..joystick
+            })
+        });
+        self.fire = self.fire.filter(|id| position(*id).is_some());
+        self.pause_pressed = false;
+
+        for (id, position) in touches {
+            if self.previous.contains(id) {
+                continue;
+            }
+            if position.distance(PAUSE_BUTTON_CENTER) <= PAUSE_BUTTON_RADIUS {
+                self.pause_pressed = true;
+            } else if position.distance(FIRE_BUTTON_CENTER) <= FIRE_BUTTON_RADIUS {
+                self.fire.get_or_insert(*id);
+            } else if position.x < VIRTUAL_WIDTH / 2. && self.joystick.is_none() {
+                self.joystick = Some(Joystick {
+                    touch: *id,
+                    origin: *position,
+                    position: *position,
+                });
+            }
+        }
+
+        self.previous = touches.iter().map(|(id, _)| *id).collect();
+    }
+
+    /// which way and how far the joystick is pushed, up to a length of 1
+    pub fn movement(&self) -> Vec2 {
+        match self.joystick {
+            Some(joystick) => {
+                let offset = (joystick.position - joystick.origin) / JOYSTICK_RADIUS;
+                apply_deadzone(offset.clamp_length_max(1.), JOYSTICK_DEADZONE, false)
+            }
+            None => Vec2::ZERO,
+        }
+    }
+
+    pub fn fire(&self) -> bool {
+        self.fire.is_some()
+    }
+
+    /// the pause button was just tapped
+    pub fn pause_pressed(&self) -> bool {
+        self.pause_pressed
+    }
+
+    pub fn draw(&self) {
+        if !self.visible {
+            return;
+        }
+
+        if let Some(joystick) = self.joystick {
+            let knob = joystick.origin + self.movement() * JOYSTICK_RADIUS;
+            draw_circle_lines(
+                joystick.origin.x,
+                joystick.origin.y,
+                JOYSTICK_RADIUS,
+                3.,
+                CONTROLS_COLOR,
+            );
+            draw_circle(knob.x, knob.y, JOYSTICK_RADIUS * 0.4, CONTROLS_ACTIVE_COLOR);
+        }
+
+        let fire_color = if self.fire() {
+            CONTROLS_ACTIVE_COLOR
+        } else {
+            CONTROLS_COLOR
+        };
+        draw_circle(
+            FIRE_BUTTON_CENTER.x,
+            FIRE_BUTTON_CENTER.y,
+            FIRE_BUTTON_RADIUS,
+            fire_color,
+        );
+
+        // two bars, like a pause symbol
+        draw_circle_lines(
+            PAUSE_BUTTON_CENTER.x,
+            PAUSE_BUTTON_CENTER.y,
+            PAUSE_BUTTON_RADIUS,
+            3.,
+            CONTROLS_COLOR,
+        );
+        for offset in [-10., 4.] {
+            draw_rectangle(
+                PAUSE_BUTTON_CENTER.x + offset,
+                PAUSE_BUTTON_CENTER.y - 12.,
+                6.,
+                24.,
+                CONTROLS_COLOR,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_joystick_follows_drag() {
+        let mut touch = TouchControls::default();
+        let start = Vec2::new(200., 500.);
+        touch.update(&[(1, start)]);
+        assert!(touch.visible);
+        assert_eq!(touch.movement(), Vec2::ZERO);
+
+        touch.update(&[(1, start + Vec2::new(JOYSTICK_RADIUS, 0.))]);
+        assert_eq!(touch.movement(), Vec2::X);
+
+        // dragging past the edge doesn't go faster
+        touch.update(&[(1, start + Vec2::new(0., -JOYSTICK_RADIUS * 3.))]);
+        assert_eq!(touch.movement(), Vec2::NEG_Y);
+
+        touch.update(&[]);
+        assert_eq!(touch.movement(), Vec2::ZERO);
+    }
+
+    #[test]
+    fn test_fire_while_moving() {
+        let mut touch = TouchControls::default();
+        let start = Vec2::new(200., 500.);
+        touch.update(&[(1, start)]);
+        touch.update(&[(1, start + Vec2::Y * 40.), (2, FIRE_BUTTON_CENTER)]);
+        assert!(touch.fire());
+        assert!(touch.movement().y > 0.);
+
+        // sliding off the button keeps firing until the finger is lifted
+        touch.update(&[(1, start), (2, FIRE_BUTTON_CENTER - 200.)]);
+        assert!(touch.fire());
+        touch.update(&[(1, start)]);
+        assert!(!touch.fire());
+    }
+
+    #[test]
+    fn test_pause_only_on_new_touch() {
+        let mut touch = TouchControls::default();
+        touch.update(&[(1, PAUSE_BUTTON_CENTER)]);
+        assert!(touch.pause_pressed());
+        touch.update(&[(1, PAUSE_BUTTON_CENTER)]);
+        assert!(!touch.pause_pressed());
+    }
+
+    #[test]
+    fn test_right_side_does_not_start_joystick() {
+        let mut touch = TouchControls::default();
+        touch.update(&[(1, Vec2::new(VIRTUAL_WIDTH * 0.75, 100.))]);
+        touch.update(&[(1, Vec2::new(VIRTUAL_WIDTH * 0.75, 300.))]);
+        assert_eq!(touch.movement(), Vec2::ZERO);
+        assert!(!touch.fire());
+    }
+}
@@ -0,0 +1,78 @@
+use macroquad::math::Vec2;
+use macroquad::window::{screen_height, screen_width};
+
+use crate::consts::{VIRTUAL_HEIGHT, VIRTUAL_WIDTH};
+
+/// where the virtual screen is drawn in the window: scaled to fit and centered, with bars filling
+/// the rest
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Letterbox {
+    pub scale: f32,
+    /// top left corner of the virtual screen in the window
+    pub offset: Vec2,
+}
+
+impl Letterbox {
+    pub fn new(window_width: f32, window_height: f32) -> Self {
+        let scale = f32::min(window_width / VIRTUAL_WIDTH, window_height / VIRTUAL_HEIGHT);
+        let offset = Vec2::new(
+            (window_width - (VIRTUAL_WIDTH * scale)) * 0.5,
+            (window_height - (VIRTUAL_HEIGHT * scale)) * 0.5,
+        );
+        Self { scale, offset }
+    }
+
+    /// the letterbox for the window's current size
+    pub fn current() -> Self {
+        Self::new(screen_width(), screen_height())
+    }
+
+    /// the size the virtual screen is drawn at in the window
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(VIRTUAL_WIDTH, VIRTUAL_HEIGHT) * self.scale
+    }
+
+    /// converts a position in the window, like the mouse's, to the virtual screen's coordinates
+    pub fn to_virtual(&self, window_position: Vec2) -> Vec2 {
+        (window_position - self.offset) / self.scale
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_fit() {
+        let letterbox = Letterbox::new(VIRTUAL_WIDTH * 2., VIRTUAL_HEIGHT * 2.);
+        assert_eq!(letterbox.scale, 2.);
+        assert_eq!(letterbox.offset, Vec2::ZERO);
+        assert_eq!(
+            letterbox.to_virtual(Vec2::new(200., 100.)),
+            Vec2::new(100., 50.)
+        );
+    }
+
+    #[test]
+    fn test_bars_on_the_sides() {
+        // twice as wide as it needs to be for its height
+        let letterbox = Letterbox::new(VIRTUAL_WIDTH * 2., VIRTUAL_HEIGHT);
+        assert_eq!(letterbox.scale, 1.);
+        assert_eq!(letterbox.offset, Vec2::new(VIRTUAL_WIDTH / 2., 0.));
+        assert_eq!(letterbox.to_virtual(letterbox.offset), Vec2::ZERO);
+        // the bars are outside of the virtual screen
+        assert!(letterbox.to_virtual(Vec2::new(10., 10.)).x < 0.);
+    }
+
+    #[test]
+    fn test_bars_above_and_below() {
+        let letterbox = Letterbox::new(VIRTUAL_WIDTH / 2., VIRTUAL_HEIGHT);
+        assert_eq!(letterbox.scale, 0.5);
+        assert_eq!(letterbox.offset, Vec2::new(0., VIRTUAL_HEIGHT / 4.));
+        let bottom_right = letterbox.offset + letterbox.size();
+        assert_eq!(
+            letterbox.to_virtual(bottom_right),
+            Vec2::new(VIRTUAL_WIDTH, VIRTUAL_HEIGHT)
+        );
+    }
+}
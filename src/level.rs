@@ -0,0 +1,142 @@
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::asset_error::{read_asset_string, AssetError};
+use crate::manifest::Manifest;
+use crate::math::Segment;
+
+/// dir within the assets dir that holds the level files
+pub const LEVELS_DIR: &str = "levels";
+/// file within the levels dir listing the level files in the order they're played
+const LEVELS_INDEX_FILE: &str = "levels.ron";
+
+/// the layout and difficulty of a single level, loaded from a RON file in `assets/levels/`
+#[derive(Clone, Debug, Deserialize)]
+pub struct Level {
+    /// identifies the level in save data, taken from its file name
+    #[serde(skip)]
+    pub id: String,
+    /// displayed to the player
+    pub name: String,
+    /// seconds it takes for the asteroid spawn rate to ramp up from its start to its end
+    pub duration: f32,
+    /// seconds between asteroids spawning, over the course of the level
+    pub spawn_interval: Curve,
+    /// seconds between warp crystals spawning
+    pub crystal_spawn_interval: f32,
+    pub asteroid_width: Range,
+    pub asteroid_height: Range,
+    /// how fast asteroids fly across the screen, in pixels per second
+    pub asteroid_speed: Range,
+    /// how many warp crystals need to be collected to complete the level
+    pub crystal_quota: u32,
+    /// static segments that block light
+    pub walls: Vec<Segment>,
+    /// id of the looping sound in the asset manifest played during the level, silence if there's none
+    #[serde(default)]
+    pub music: Option<String>,
+    /// textures, sounds and fonts only this level uses, loaded the first time it's played instead
+    /// of at startup; paths are relative to the assets dir, like the asset manifest's
+    #[serde(default)]
+    pub assets: Manifest,
+}
+
+/// a value that changes linearly over the course of the level
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct Curve {
+    pub start: f32,
+    pub end: f32,
+}
+
+impl Curve {
+    /// the value at `progress`, from 0 (start of the level) to 1 (its duration has passed)
+    pub fn at(&self, progress: f32) -> f32 {
+        self.start + (self.end - self.start) * progress.clamp(0., 1.)
+    }
+}
+
+/// the bounds to pick a random value between
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct Range {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Range {
+    /// the value at `ratio` of the way from min to max
+    pub fn sample(&self, ratio: f32) -> f32 {
+        self.min + (self.max - self.min) * ratio
+    }
+}
+
+/// loads every level listed in the levels index, in order
+///
+/// Levels that can't be read or parsed are left out and added to `errors`, as are all of them
+/// if the index can't be
+pub async fn load_levels(base_assets_path: &Path, errors: &mut Vec<AssetError>) -> Vec<Level> {
+    let levels_path = base_assets_path.join(LEVELS_DIR);
+    let index: Vec<String> =
+        match load_ron("levels index", &levels_path.join(LEVELS_INDEX_FILE)).await {
+            Ok(index) => index,
+            Err(e) => {
+                errors.push(e);
+                return vec![];
+            }
+        };
+
+    let mut levels = vec![];
+    for file in index {
+        match load_ron::<Level>(&format!("level {file}"), &levels_path.join(&file)).await {
+            Ok(mut level) => {
+                level.id = file.trim_end_matches(".ron").to_string();
+                levels.push(level);
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+    levels
+}
+
+async fn load_ron<T: serde::de::DeserializeOwned>(id: &str, path: &Path) -> Result<T, AssetError> {
+    let ron_str = read_asset_string(id, path).await?;
+    ron::from_str(&ron_str).map_err(|e| AssetError::Decode {
+        id: id.to_string(),
+        path: path.to_path_buf(),
+        reason: e.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets_path::ASSETS_DIR;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_levels_parse() {
+        let levels_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join(ASSETS_DIR)
+            .join(LEVELS_DIR);
+        let index: Vec<String> =
+            ron::from_str(&std::fs::read_to_string(levels_path.join(LEVELS_INDEX_FILE)).unwrap())
+                .unwrap();
+        assert!(!index.is_empty());
+
+        for file in index {
+            let level_str = std::fs::read_to_string(levels_path.join(&file)).unwrap();
+            let level: Result<Level, _> = ron::from_str(&level_str);
+            assert!(level.is_ok(), "{file}: {:?}", level.err());
+        }
+    }
+
+    #[test]
+    fn test_curve() {
+        let curve = Curve { start: 2., end: 1. };
+        assert_eq!(curve.at(0.), 2.);
+        assert_eq!(curve.at(0.5), 1.5);
+        assert_eq!(curve.at(1.), 1.);
+        // holds at the end once the duration has passed
+        assert_eq!(curve.at(3.), 1.);
+    }
+}
@@ -1,3 +1,4 @@
+pub mod asset_error;
 pub mod assets_path;
 pub mod audio;
 pub mod color;
@@ -6,9 +7,19 @@ pub mod context;
 pub mod dir;
 pub mod font;
 pub mod input;
+pub mod letterbox;
+pub mod level;
+pub mod light;
+pub mod loader;
+pub mod manifest;
 pub mod math;
+pub mod menu;
+pub mod music;
+pub mod replay;
+pub mod rng;
 pub mod save;
 pub mod scene;
 pub mod settings;
 pub mod text;
 pub mod texture;
+pub mod world;
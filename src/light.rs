@@ -0,0 +1,71 @@
+use macroquad::color::Color;
+use macroquad::math::Vec2;
+use macroquad::models::{draw_mesh, Mesh, Vertex};
+
+/// how many bands each triangle of the light is split into, so the falloff is smooth instead of
+/// being linearly interpolated across the whole triangle
+const FALLOFF_BANDS: usize = 8;
+
+/// Draws a light at `origin` filling the visibility polygon (see `math::visibility_polygon`).
+///
+/// The polygon is drawn as a triangle fan around the origin. The light's influence fades with
+/// distance from the origin and is gone entirely at `radius`, like the redblob visibility demo.
+/// Anything outside of the polygon is left unlit, which is what casts the shadows.
+pub fn draw_light(origin: Vec2, polygon: &[Vec2], radius: f32, color: Color) {
+    if polygon.len() < 2 {
+        return;
+    }
+
+    for (i, a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        draw_mesh(&light_triangle_mesh(origin, *a, b, radius, color));
+    }
+}
+
+/// how bright the light is at the given distance from its origin, from 0 to 1
+fn falloff(distance: f32, radius: f32) -> f32 {
+    let t = (1. - distance / radius).clamp(0., 1.);
+    t * t
+}
+
+/// builds the mesh for a single triangle of the fan, split into bands along its two outer edges
+/// so each band's vertices get their own falloff color
+fn light_triangle_mesh(origin: Vec2, a: Vec2, b: Vec2, radius: f32, color: Color) -> Mesh {
+    let mut vertices = vec![];
+    for edge_end in [a, b] {
+        let edge = edge_end - origin;
+        let length = edge.length();
+        let dir = edge.normalize_or_zero();
+
+        // stop the bands early if the edge is shorter than the light, and make sure the last one
+        // reaches the end of the edge if it's longer
+        let mut distances: Vec<f32> = (0..=FALLOFF_BANDS)
+            .map(|band| (radius * band as f32 / FALLOFF_BANDS as f32).min(length))
+            .collect();
+        distances.push(length);
+
+        for distance in distances {
+            let p = origin + dir * distance;
+            let c = Color {
+                a: color.a * falloff(distance, radius),
+                ..color
+            };
+            vertices.push(Vertex::new(p.x, p.y, 0., 0., 0., c));
+        }
+    }
+
+    // vertices along `a` come first, then the same number along `b`
+    let per_edge = (vertices.len() / 2) as u16;
+    let mut indices = vec![];
+    for i in 0..per_edge - 1 {
+        let (a0, a1) = (i, i + 1);
+        let (b0, b1) = (per_edge + i, per_edge + i + 1);
+        indices.extend_from_slice(&[a0, b0, a1, b0, b1, a1]);
+    }
+
+    Mesh {
+        vertices,
+        indices,
+        texture: None,
+    }
+}
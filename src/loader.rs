@@ -0,0 +1,283 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::task::{self, Poll, Waker};
+
+use macroquad::text::Font;
+use macroquad::texture::Texture2D;
+use macroquad::time::get_time;
+
+use crate::asset_error::AssetError;
+use crate::audio::{self, LoadedSound};
+use crate::context::Context;
+use crate::font;
+use crate::level::{load_levels, Level};
+use crate::manifest::{FontEntry, Manifest, SoundEntry, TextureEntry};
+use crate::texture;
+
+/// seconds of each frame spent loading, so the frame rate doesn't drop too far to draw progress
+const FRAME_BUDGET: f64 = 1. / 120.;
+
+/// loads assets into the context a few at a time over however many frames it takes, so a scene
+/// can keep drawing while they come in
+pub struct Loader {
+    base_assets_path: PathBuf,
+    jobs: VecDeque<Job>,
+    /// how many jobs have finished, for the progress
+    done: usize,
+    /// the job being loaded, polled every frame until it's ready
+    current: Option<Pin<Box<dyn Future<Output = Loaded>>>>,
+}
+
+/// a single thing to load
+enum Job {
+    /// the asset manifest, queueing everything listed in it once it's read
+    Manifest,
+    Levels,
+    Texture(String, TextureEntry),
+    Sound(String, SoundEntry),
+    Font(String, FontEntry),
+}
+
+/// what a job loaded, to be put into the context
+enum Loaded {
+    Manifest(Result<Manifest, AssetError>),
+    Levels(Vec<Level>, Vec<AssetError>),
+    Texture(String, Result<Texture2D, AssetError>),
+    Sound(String, Result<LoadedSound, AssetError>),
+    Font(String, Result<Font, AssetError>),
+}
+
+/// a loaded asset that belongs in the context
+enum Asset {
+    Levels(Vec<Level>),
+    Texture(String, Texture2D),
+    Sound(String, LoadedSound),
+    Font(String, Font),
+}
+
+impl Loader {
+    /// the asset manifest, everything listed in it and the levels, for when the game starts
+    pub fn startup(base_assets_path: PathBuf) -> Self {
+        Self::new(base_assets_path, vec![Job::Manifest, Job::Levels])
+    }
+
+    /// everything listed in the manifest, for assets that are only needed some of the time, like
+    /// a level's own
+    pub fn manifest(base_assets_path: PathBuf, manifest: &Manifest) -> Self {
+        Self::new(base_assets_path, manifest_jobs(manifest))
+    }
+
+    fn new(base_assets_path: PathBuf, jobs: Vec<Job>) -> Self {
+        Self {
+            base_assets_path,
+            jobs: jobs.into(),
+            done: 0,
+            current: None,
+        }
+    }
+
+    /// loads for up to a frame's worth of time, adding each asset to the context as it's ready
+    /// and any that couldn't be loaded to its asset errors
+    pub fn update(&mut self, ctx: &mut Context) {
+        let started = get_time();
+        while get_time() - started < FRAME_BUDGET {
+            let base_assets_path = self.base_assets_path.clone();
+            let current = match &mut self.current {
+                Some(current) => current,
+                None => match self.jobs.pop_front() {
+                    Some(job) => self.current.insert(job.start(base_assets_path)),
+                    None => return,
+                },
+            };
+
+            // Macroquad's loading futures check on their own whether they're ready each time
+            // they're polled, rather than waiting to be woken
+            match current
+                .as_mut()
+                .poll(&mut task::Context::from_waker(Waker::noop()))
+            {
+                Poll::Ready(loaded) => {
+                    self.current = None;
+                    self.done += 1;
+                    if let Some(asset) = self.finish(loaded, &mut ctx.asset_errors) {
+                        asset.insert(ctx);
+                    }
+                }
+                Poll::Pending => return,
+            }
+        }
+    }
+
+    /// whether everything has been loaded
+    pub fn is_done(&self) -> bool {
+        self.current.is_none() && self.jobs.is_empty()
+    }
+
+    /// how much has been loaded, from 0 to 1; can go back a little when the manifest is read and
+    /// adds what it lists
+    pub fn progress(&self) -> f32 {
+        let total = self.done + self.jobs.len() + self.current.iter().count();
+        if total == 0 {
+            1.
+        } else {
+            self.done as f32 / total as f32
+        }
+    }
+
+    /// queues what a manifest lists and adds anything that failed to the errors, leaving the
+    /// asset, if there is one, to be put into the context
+    fn finish(&mut self, loaded: Loaded, errors: &mut Vec<AssetError>) -> Option<Asset> {
+        match loaded {
+            Loaded::Manifest(Ok(manifest)) => {
+                self.jobs.extend(manifest_jobs(&manifest));
+                None
+            }
+            Loaded::Levels(levels, level_errors) => {
+                errors.extend(level_errors);
+                Some(Asset::Levels(levels))
+            }
+            Loaded::Texture(id, Ok(texture)) => Some(Asset::Texture(id, texture)),
+            Loaded::Sound(id, Ok(sound)) => Some(Asset::Sound(id, sound)),
+            Loaded::Font(id, Ok(font)) => Some(Asset::Font(id, font)),
+            // the atlases stand in placeholders for anything they don't have
+            Loaded::Manifest(Err(e))
+            | Loaded::Texture(_, Err(e))
+            | Loaded::Sound(_, Err(e))
+            | Loaded::Font(_, Err(e)) => {
+                errors.push(e);
+                None
+            }
+        }
+    }
+}
+
+impl Asset {
+    fn insert(self, ctx: &mut Context) {
+        match self {
+            Asset::Levels(levels) => ctx.levels = levels,
+            Asset::Texture(id, texture) => ctx.textures.insert(id, texture),
+            Asset::Sound(id, LoadedSound::Sfx(sound)) => ctx.audio.insert_sfx(id, sound),
+            Asset::Sound(id, LoadedSound::Varied(varied)) => {
+                ctx.audio.insert_varied_sfx(id, varied)
+            }
+            Asset::Sound(id, LoadedSound::Music(sound)) => ctx.music.add_track(id, sound),
+            Asset::Font(id, font) => ctx.fonts.insert(id, font),
+        }
+    }
+}
+
+impl Job {
+    fn start(self, base_assets_path: PathBuf) -> Pin<Box<dyn Future<Output = Loaded>>> {
+        Box::pin(async move {
+            let base_assets_path = base_assets_path.as_path();
+            match self {
+                Job::Manifest => Loaded::Manifest(Manifest::load(base_assets_path).await),
+                Job::Levels => {
+                    let mut errors = vec![];
+                    let levels = load_levels(base_assets_path, &mut errors).await;
+                    Loaded::Levels(levels, errors)
+                }
+                Job::Texture(id, entry) => {
+                    let texture = texture::load(&id, base_assets_path, &entry).await;
+                    Loaded::Texture(id, texture)
+                }
+                Job::Sound(id, entry) => {
+                    let sound = audio::load(&id, base_assets_path, &entry).await;
+                    Loaded::Sound(id, sound)
+                }
+                Job::Font(id, entry) => {
+                    let font = font::load(&id, base_assets_path, &entry).await;
+                    Loaded::Font(id, font)
+                }
+            }
+        })
+    }
+}
+
+/// fonts first, so the loading screen's text switches to them as soon as it can
+fn manifest_jobs(manifest: &Manifest) -> Vec<Job> {
+    let fonts = manifest
+        .fonts
+        .iter()
+        .map(|(id, entry)| Job::Font(id.clone(), entry.clone()));
+    let textures = manifest
+        .textures
+        .iter()
+        .map(|(id, entry)| Job::Texture(id.clone(), entry.clone()));
+    let sounds = manifest
+        .sounds
+        .iter()
+        .map(|(id, entry)| Job::Sound(id.clone(), entry.clone()));
+    fonts.chain(textures).chain(sounds).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress() {
+        let loader = Loader::startup(PathBuf::from("assets"));
+        assert!(!loader.is_done());
+        assert_eq!(loader.progress(), 0.);
+
+        let mut loader = Loader::new(PathBuf::from("assets"), vec![]);
+        assert!(loader.is_done());
+        assert_eq!(loader.progress(), 1.);
+
+        loader.done = 1;
+        loader.jobs.push_back(Job::Levels);
+        assert_eq!(loader.progress(), 0.5);
+    }
+
+    #[test]
+    fn test_finish_queues_manifest_and_collects_errors() {
+        let manifest: Manifest = ron::from_str(
+            r#"(
+                textures: {"ship": (path: "ship.png")},
+                sounds: {"shoot": (path: "shoot.wav")},
+                fonts: {"regular": (path: "regular.ttf")},
+            )"#,
+        )
+        .unwrap();
+        let mut loader = Loader::new(PathBuf::from("assets"), vec![]);
+        let mut errors = vec![];
+
+        assert!(loader
+            .finish(Loaded::Manifest(Ok(manifest)), &mut errors)
+            .is_none());
+        let queued: Vec<&str> = loader
+            .jobs
+            .iter()
+            .map(|job| match job {
+                Job::Texture(id, _) | Job::Sound(id, _) | Job::Font(id, _) => id.as_str(),
+                Job::Manifest | Job::Levels => panic!("only the manifest's assets are queued"),
+            })
+            .collect();
+        assert_eq!(queued, ["regular", "ship", "shoot"]);
+        assert!(errors.is_empty());
+
+        let error = AssetError::Decode {
+            id: "ship".to_string(),
+            path: PathBuf::from("assets/ship.png"),
+            reason: "bad".to_string(),
+        };
+        assert!(loader
+            .finish(Loaded::Texture("ship".to_string(), Err(error)), &mut errors)
+            .is_none());
+        assert!(loader
+            .finish(
+                Loaded::Manifest(Err(AssetError::Decode {
+                    id: "manifest".to_string(),
+                    path: PathBuf::from("assets/manifest.ron"),
+                    reason: "bad".to_string(),
+                })),
+                &mut errors
+            )
+            .is_none());
+        assert_eq!(errors.len(), 2);
+        assert_eq!(loader.jobs.len(), 3);
+    }
+}
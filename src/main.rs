@@ -4,7 +4,12 @@ use macroquad::prelude::*;
 use miniquad::conf::Icon;
 use quads_jam_2024::consts::*;
 use quads_jam_2024::context::Context;
+use quads_jam_2024::input::FrameInput;
+use quads_jam_2024::letterbox::Letterbox;
+use quads_jam_2024::loader::Loader;
+use quads_jam_2024::scene::asset_errors::AssetErrors;
 use quads_jam_2024::scene::gameplay::Gameplay;
+use quads_jam_2024::scene::loading::Loading;
 use quads_jam_2024::scene::EScene;
 use quads_jam_2024::scene::{main_menu::MainMenu, Scene};
 
@@ -31,8 +36,13 @@ async fn main() {
         ..Context::default().await
     };
 
-    // TODO: for now, just boot into gameplay
-    let mut current_scene: Box<dyn Scene> = Box::new(Gameplay::new(&mut ctx).await);
+    let mut current_scene: Box<dyn Scene> = Box::new(Loading::new(
+        Loader::startup(ctx.assets_path.clone()),
+        EScene::MainMenu,
+    ));
+
+    // frame time that hasn't been simulated yet
+    let mut accumulator = 0.;
 
     loop {
         ///////// UPDATE
@@ -44,7 +54,18 @@ async fn main() {
         }
 
         ctx.gamepads.poll();
+        let frame_input = FrameInput::read(&ctx.gamepads, &ctx.settings);
+        ctx.input.advance(frame_input, get_frame_time());
         current_scene.update(&mut ctx);
+        ctx.music.update(&ctx.settings, get_frame_time());
+
+        // step the simulation at a fixed rate, so it behaves the same at any frame rate
+        accumulator += get_frame_time().min(MAX_FRAME_TIME);
+        while accumulator >= FIXED_TIMESTEP {
+            current_scene.fixed_update(&mut ctx, FIXED_TIMESTEP);
+            accumulator -= FIXED_TIMESTEP;
+        }
+        ctx.interpolation = accumulator / FIXED_TIMESTEP;
 
         ///////// DRAW
 
@@ -58,17 +79,14 @@ async fn main() {
         clear_background(quads_jam_2024::color::DARKGRAY); // Will be the letterbox color
 
         // draw the render target properly scaled and letterboxed
-        let scale: f32 = f32::min(
-            screen_width() / VIRTUAL_WIDTH,
-            screen_height() / VIRTUAL_HEIGHT,
-        );
+        let letterbox = Letterbox::current();
         draw_texture_ex(
             &ctx.render_target.texture,
-            (screen_width() - (VIRTUAL_WIDTH * scale)) * 0.5,
-            (screen_height() - (VIRTUAL_HEIGHT * scale)) * 0.5,
+            letterbox.offset.x,
+            letterbox.offset.y,
             WHITE,
             DrawTextureParams {
-                dest_size: Some(vec2(VIRTUAL_WIDTH * scale, VIRTUAL_HEIGHT * scale)),
+                dest_size: Some(letterbox.size()),
                 flip_y: true, // Must flip y otherwise 'render_target' will be upside down
                 ..Default::default()
             },
@@ -81,9 +99,20 @@ async fn main() {
         if let Some(escene) = ctx.switch_scene_to.clone() {
             current_scene = match escene {
                 EScene::MainMenu => Box::new(MainMenu::new(&mut ctx).await),
-                EScene::Gameplay => Box::new(Gameplay::new(&mut ctx).await),
+                // through the loading screen the first time a level with its own assets is played
+                EScene::Gameplay(level) => match Loading::for_level(&mut ctx, level) {
+                    Some(loading) => Box::new(loading),
+                    None => Box::new(Gameplay::new(&mut ctx, level).await),
+                },
+                // back to the main menu if there's no replay this version can play
+                EScene::Replay => match Gameplay::watch_last_replay(&mut ctx).await {
+                    Some(gameplay) => Box::new(gameplay),
+                    None => Box::new(MainMenu::new(&mut ctx).await),
+                },
+                EScene::AssetErrors(next) => Box::new(AssetErrors::new(&ctx, *next)),
             };
             ctx.switch_scene_to = None;
+            accumulator = 0.;
         }
 
         next_frame().await
@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use macroquad::miniquad::FilterMode;
+use serde::Deserialize;
+
+use crate::asset_error::{read_asset_string, AssetError};
+
+/// file within the assets dir listing every asset to load
+const MANIFEST_FILE: &str = "manifest.ron";
+
+/// every texture, sound and font the game loads, by the id the code looks them up with
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Manifest {
+    #[serde(default)]
+    pub textures: BTreeMap<String, TextureEntry>,
+    #[serde(default)]
+    pub sounds: BTreeMap<String, SoundEntry>,
+    #[serde(default)]
+    pub fonts: BTreeMap<String, FontEntry>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TextureEntry {
+    /// relative to the assets dir
+    pub path: String,
+    #[serde(default)]
+    pub filter: Filter,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SoundEntry {
+    /// relative to the assets dir
+    pub path: String,
+    /// loops as a music track, on the music volume bus, instead of playing once as an effect
+    #[serde(default)]
+    pub looping: bool,
+    /// plays at a random pitch and panned to where it happened, needs to be a 16-bit PCM WAV
+    #[serde(default)]
+    pub varied: bool,
+    /// the most copies of a varied sound that can play at once
+    #[serde(default = "default_max_voices")]
+    pub max_voices: usize,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct FontEntry {
+    /// relative to the assets dir
+    pub path: String,
+    #[serde(default)]
+    pub filter: Filter,
+}
+
+/// how a texture or font is scaled; `FilterMode` can't be deserialized
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+pub enum Filter {
+    /// smooth, for fonts and detailed images
+    #[default]
+    Linear,
+    /// blocky, for pixel art
+    Nearest,
+}
+
+fn default_max_voices() -> usize {
+    4
+}
+
+impl From<Filter> for FilterMode {
+    fn from(filter: Filter) -> Self {
+        match filter {
+            Filter::Linear => FilterMode::Linear,
+            Filter::Nearest => FilterMode::Nearest,
+        }
+    }
+}
+
+impl Manifest {
+    /// whether it doesn't list anything to load
+    pub fn is_empty(&self) -> bool {
+        self.textures.is_empty() && self.sounds.is_empty() && self.fonts.is_empty()
+    }
+
+    /// reads the manifest from the assets dir
+    pub async fn load(base_assets_path: &Path) -> Result<Self, AssetError> {
+        let path = base_assets_path.join(MANIFEST_FILE);
+        let manifest_str = read_asset_string("asset manifest", &path).await?;
+        Self::parse(&manifest_str).map_err(|e| AssetError::Decode {
+            id: "asset manifest".to_string(),
+            path,
+            reason: e.to_string(),
+        })
+    }
+
+    fn parse(manifest_str: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str(manifest_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assets_dir() -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("assets")
+    }
+
+    fn manifest() -> Manifest {
+        Manifest::parse(&std::fs::read_to_string(assets_dir().join(MANIFEST_FILE)).unwrap())
+            .unwrap()
+    }
+
+    /// the startup manifest, then each level's own assets
+    fn all_manifests() -> Vec<Manifest> {
+        let levels_dir = assets_dir().join(crate::level::LEVELS_DIR);
+        let mut manifests = vec![manifest()];
+        for file in std::fs::read_dir(levels_dir).unwrap() {
+            let path = file.unwrap().path();
+            if path.file_name().unwrap() == "levels.ron" {
+                continue;
+            }
+            let level: crate::level::Level =
+                ron::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+            manifests.push(level.assets);
+        }
+        manifests
+    }
+
+    #[test]
+    fn test_every_asset_exists() {
+        for manifest in all_manifests() {
+            assert_every_asset_exists(&manifest);
+        }
+    }
+
+    fn assert_every_asset_exists(manifest: &Manifest) {
+        let paths = manifest
+            .textures
+            .values()
+            .map(|t| &t.path)
+            .chain(manifest.sounds.values().map(|s| &s.path))
+            .chain(manifest.fonts.values().map(|f| &f.path));
+        for path in paths {
+            assert!(assets_dir().join(path).is_file(), "{path}");
+        }
+    }
+
+    #[test]
+    fn test_varied_sounds_decode() {
+        for manifest in all_manifests() {
+            for (id, sound) in manifest.sounds.iter().filter(|(_, s)| s.varied) {
+                let bytes = std::fs::read(assets_dir().join(&sound.path)).unwrap();
+                let pcm = crate::audio::variants::decode_wav(&bytes).unwrap();
+                assert!(pcm.duration() > 0., "{id}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_sounds_are_playable() {
+        for manifest in all_manifests() {
+            for (id, sound) in manifest.sounds.iter() {
+                let bytes = std::fs::read(assets_dir().join(&sound.path)).unwrap();
+                assert_eq!(crate::audio::check_sound_format(&bytes), Ok(()), "{id}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_level_music_is_listed() {
+        let manifest = manifest();
+        let levels_dir = assets_dir().join(crate::level::LEVELS_DIR);
+        for file in std::fs::read_dir(levels_dir).unwrap() {
+            let path = file.unwrap().path();
+            if path.file_name().unwrap() == "levels.ron" {
+                continue;
+            }
+            let level: crate::level::Level =
+                ron::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+            if let Some(music) = level.music {
+                let sound = level
+                    .assets
+                    .sounds
+                    .get(&music)
+                    .or(manifest.sounds.get(&music))
+                    .unwrap_or_else(|| panic!("{music} isn't listed"));
+                assert!(sound.looping, "{}", path.display());
+            }
+        }
+    }
+
+    #[test]
+    fn test_ids_used_in_code_are_listed() {
+        let manifest = manifest();
+        let sound = |id: &str| {
+            manifest
+                .sounds
+                .get(id)
+                .unwrap_or_else(|| panic!("{id} isn't listed"))
+        };
+        for id in crate::audio::SFX {
+            assert!(!sound(id).looping && !sound(id).varied, "{id}");
+        }
+        for id in crate::audio::VARIED_SFX {
+            assert!(sound(id).varied, "{id}");
+        }
+        for id in [crate::music::MENU_TRACK, crate::music::GAME_OVER_TRACK] {
+            assert!(sound(id).looping, "{id}");
+        }
+        assert!(manifest.fonts.contains_key(crate::font::REGULAR));
+    }
+
+    #[test]
+    fn test_options_default() {
+        let manifest = Manifest::parse(
+            r#"(sounds: {"blip": (path: "blip.wav")}, textures: {"a": (path: "a.png")})"#,
+        )
+        .unwrap();
+        let blip = &manifest.sounds["blip"];
+        assert!(!blip.looping && !blip.varied);
+        assert_eq!(manifest.textures["a"].filter, Filter::Linear);
+        assert!(manifest.fonts.is_empty());
+    }
+}
@@ -1,15 +1,36 @@
-use macroquad::math::Vec2;
+use macroquad::math::{Rect, Vec2};
+use serde::Deserialize;
 
+/// how far (in radians) to either side of a segment endpoint to cast the extra rays that slip
+/// past the corner and hit whatever is behind it
+const ENDPOINT_ANGLE_OFFSET: f32 = 0.0001;
+/// points closer than this to a line are treated as lying on it, and segments that stray less
+/// than this from parallel to a ray are treated as parallel
+const DISTANCE_EPSILON: f32 = 1e-3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Ray {
     pub origin: Vec2,
     pub dir: Vec2,
 }
 
+/// deserialized from a pair of points, like `((0, 0), (100, 0))`
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(from = "((f32, f32), (f32, f32))")]
 pub struct Segment {
     pub src: Vec2,
     pub dst: Vec2,
 }
 
+impl From<((f32, f32), (f32, f32))> for Segment {
+    fn from((src, dst): ((f32, f32), (f32, f32))) -> Self {
+        Self {
+            src: Vec2::from(src),
+            dst: Vec2::from(dst),
+        }
+    }
+}
+
 impl Segment {
     // returns the Direction vector of the line in parametric form (Point + Direction * Time)
     fn parametric_dir(&self) -> Vec2 {
@@ -17,6 +38,28 @@ impl Segment {
     }
 }
 
+/// returns the four edges of the rect as segments
+pub fn rect_segments(r: Rect) -> [Segment; 4] {
+    [
+        Segment {
+            src: Vec2::new(r.x, r.y),
+            dst: Vec2::new(r.x + r.w, r.y),
+        },
+        Segment {
+            src: Vec2::new(r.x, r.y),
+            dst: Vec2::new(r.x, r.y + r.h),
+        },
+        Segment {
+            src: Vec2::new(r.x, r.y + r.h),
+            dst: Vec2::new(r.x + r.w, r.y + r.h),
+        },
+        Segment {
+            src: Vec2::new(r.x + r.w, r.y),
+            dst: Vec2::new(r.x + r.w, r.y + r.h),
+        },
+    ]
+}
+
 fn cross(v: Vec2, w: Vec2) -> f32 {
     v.x * w.y - v.y * w.x
 }
@@ -30,29 +73,49 @@ impl Ray {
         let q = segment.src;
         let s = segment.parametric_dir();
 
+        // a ray without a direction doesn't go anywhere
+        if r.length_squared() <= f32::EPSILON {
+            return None;
+        }
+
+        // a zero-length segment is a single point, which the ray either passes through or not
+        if s.length_squared() <= f32::EPSILON {
+            return self.passes_through(q).then_some(q);
+        }
+
         // intersect if p + tr = q + us, where t and u are "time" params
         // (p + t r) × s = (q + u s) × s
         // ->
         // t = (q − p) × s / (r × s)
         // u = (q − p) × r / (r × s)
         let r_x_s = cross(r, s);
-        let t = cross(q - p, s) / r_x_s;
-        let u = cross(q - p, r) / r_x_s;
+        let q_p_x_r = cross(q - p, r);
 
-        let check1 = r_x_s == 0.;
-        let check2 = cross(q - p, r) == 0.;
+        // divided by |r|, these are how far the segment strays from parallel to the ray and how far
+        // its src is from the ray's line. comparing against a tolerance instead of exactly 0 stops
+        // nearly parallel lines from producing huge, flickering values of t
+        let check1 = r_x_s.abs() <= DISTANCE_EPSILON * r.length();
+        let check2 = q_p_x_r.abs() <= DISTANCE_EPSILON * r.length();
 
         match (check1, check2) {
             (true, true) => {
-                // the lines are collinear (overlapping)
-                // TODO: Ignoring collision for now. Need to compute actual intersection
-                return None;
+                // the lines are collinear (overlapping), so project the segment onto the ray and
+                // take the nearest part of it that's in front of the ray's origin
+                let r_dot_r = r.dot(r);
+                let t0 = (q - p).dot(r) / r_dot_r;
+                let t1 = t0 + s.dot(r) / r_dot_r;
+                let (t_min, t_max) = (t0.min(t1), t0.max(t1));
+                if t_max >= 0. {
+                    return Some(p + t_min.max(0.) * r);
+                }
             }
             (true, false) => {
                 // parallel and non-intersecting
                 return None;
             }
             (false, _) => {
+                let t = cross(q - p, s) / r_x_s;
+                let u = q_p_x_r / r_x_s;
                 // ray can extend infinitely from its source (t >= 0), but segment is bounded by its endpoints (0 <= u <= 1)
                 if t >= 0. && (0. ..=1.).contains(&u) {
                     return Some(p + t * r);
@@ -62,12 +125,75 @@ impl Ray {
 
         None
     }
+
+    /// whether the point lies on the ray, at or in front of its origin
+    fn passes_through(&self, point: Vec2) -> bool {
+        let to_point = point - self.origin;
+        cross(to_point, self.dir).abs() <= DISTANCE_EPSILON * self.dir.length()
+            && to_point.dot(self.dir) >= 0.
+    }
+
+    /// the intersection closest to the ray's origin across all of the segments
+    pub fn nearest_intersection(&self, segments: &[Segment]) -> Option<Vec2> {
+        segments
+            .iter()
+            .filter_map(|s| self.intersection(s))
+            .min_by(|a, b| {
+                self.origin
+                    .distance_squared(*a)
+                    .total_cmp(&self.origin.distance_squared(*b))
+            })
+    }
+}
+
+/// Returns the polygon of everything visible from `origin`, with its vertices sorted by angle
+/// around the origin so that consecutive vertices (wrapping around) form a triangle fan.
+///
+/// Rather than sweeping a fixed number of rays, this only casts rays at each segment endpoint
+/// and slightly to either side of it, which is enough to find every corner of the polygon.
+/// See https://ncase.me/sight-and-light/
+///
+/// The segments need to enclose the origin (e.g. include the screen bounds) for the polygon to
+/// be watertight; rays that escape without hitting anything are dropped.
+pub fn visibility_polygon(origin: Vec2, segments: &[Segment]) -> Vec<Vec2> {
+    let mut angles = vec![];
+    for s in segments {
+        for endpoint in [s.src, s.dst] {
+            let to_endpoint = endpoint - origin;
+            let angle = to_endpoint.y.atan2(to_endpoint.x);
+            angles.push(angle - ENDPOINT_ANGLE_OFFSET);
+            angles.push(angle);
+            angles.push(angle + ENDPOINT_ANGLE_OFFSET);
+        }
+    }
+    // segments often share endpoints (e.g. the corners of a rect), no need to cast those twice
+    angles.sort_by(f32::total_cmp);
+    angles.dedup();
+
+    let mut hits: Vec<(f32, Vec2)> = vec![];
+    for angle in angles {
+        let ray = Ray {
+            origin,
+            dir: Vec2::from_angle(angle),
+        };
+        if let Some(hit) = ray.nearest_intersection(segments) {
+            // normalize so the offset rays near ±PI still sort into the right place
+            hits.push((ray.dir.y.atan2(ray.dir.x), hit));
+        }
+    }
+    hits.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut polygon: Vec<Vec2> = hits.into_iter().map(|(_, hit)| hit).collect();
+    polygon.dedup_by(|a, b| a.distance_squared(*b) < f32::EPSILON);
+    polygon
 }
 
 // rust unit test
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+    use std::f32::consts::PI;
     #[test]
     fn test_ray_intersection() {
         let ray = Ray {
@@ -120,4 +246,268 @@ mod tests {
         };
         assert_eq!(ray.intersection(&seg), Some(Vec2::new(100., 30.)));
     }
+
+    #[test]
+    fn test_ray_intersection_collinear() {
+        let ray = Ray {
+            origin: Vec2::new(0., 0.),
+            dir: Vec2::new(1., 0.),
+        };
+        // in front of the ray, in either orientation
+        let seg = Segment {
+            src: Vec2::new(5., 0.),
+            dst: Vec2::new(2., 0.),
+        };
+        assert_eq!(ray.intersection(&seg), Some(Vec2::new(2., 0.)));
+        // overlapping the origin
+        let seg = Segment {
+            src: Vec2::new(-2., 0.),
+            dst: Vec2::new(2., 0.),
+        };
+        assert_eq!(ray.intersection(&seg), Some(Vec2::new(0., 0.)));
+        // behind the ray
+        let seg = Segment {
+            src: Vec2::new(-5., 0.),
+            dst: Vec2::new(-2., 0.),
+        };
+        assert_eq!(ray.intersection(&seg), None);
+    }
+
+    #[test]
+    fn test_ray_intersection_zero_length() {
+        let ray = Ray {
+            origin: Vec2::new(0., 0.),
+            dir: Vec2::new(1., 0.),
+        };
+        let point = Segment {
+            src: Vec2::new(3., 0.),
+            dst: Vec2::new(3., 0.),
+        };
+        assert_eq!(ray.intersection(&point), Some(Vec2::new(3., 0.)));
+        let point = Segment {
+            src: Vec2::new(3., 1.),
+            dst: Vec2::new(3., 1.),
+        };
+        assert_eq!(ray.intersection(&point), None);
+
+        let no_dir = Ray {
+            origin: Vec2::new(0., 0.),
+            dir: Vec2::ZERO,
+        };
+        let seg = Segment {
+            src: Vec2::new(-1., -1.),
+            dst: Vec2::new(1., 1.),
+        };
+        assert_eq!(no_dir.intersection(&seg), None);
+    }
+
+    fn approx_eq(a: Vec2, b: Vec2) -> bool {
+        // f32 loses precision fast, so allow more error the further out the points are
+        a.distance(b) <= 1e-3 * (1. + a.length().max(b.length()))
+    }
+
+    /// a ray from anywhere nearby, pointing in any direction, with a non-normalized direction
+    fn any_ray() -> impl Strategy<Value = Ray> {
+        (-100f32..100., -100f32..100., 0f32..2. * PI, 0.1f32..10.).prop_map(|(x, y, angle, len)| {
+            Ray {
+                origin: Vec2::new(x, y),
+                dir: Vec2::from_angle(angle) * len,
+            }
+        })
+    }
+
+    /// builds a segment along `dir` where `point` is at the fraction `u` of the way from src to dst
+    fn segment_through(point: Vec2, dir: Vec2, u: f32) -> Segment {
+        let src = point - u * dir;
+        Segment {
+            src,
+            dst: src + dir,
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn prop_crossing_segment_hits(
+            ray in any_ray(),
+            t in 0.1f32..100.,
+            // keep away from parallel, that's covered separately
+            angle in 0.1f32..PI - 0.1,
+            seg_len in 0.5f32..100.,
+            u in 0.01f32..0.99,
+        ) {
+            let hit = ray.origin + t * ray.dir;
+            let seg_dir = Vec2::from_angle(angle).rotate(ray.dir.normalize()) * seg_len;
+            let seg = segment_through(hit, seg_dir, u);
+            let result = ray.intersection(&seg);
+            prop_assert!(result.is_some_and(|r| approx_eq(r, hit)), "{result:?} != {hit}");
+        }
+
+        #[test]
+        fn prop_crossing_behind_misses(
+            ray in any_ray(),
+            t in 0.1f32..100.,
+            angle in 0.1f32..PI - 0.1,
+            seg_len in 0.5f32..100.,
+            u in 0.01f32..0.99,
+        ) {
+            let behind = ray.origin - t * ray.dir;
+            let seg_dir = Vec2::from_angle(angle).rotate(ray.dir.normalize()) * seg_len;
+            let seg = segment_through(behind, seg_dir, u);
+            prop_assert_eq!(ray.intersection(&seg), None);
+        }
+
+        #[test]
+        fn prop_line_crossing_past_segment_end_misses(
+            ray in any_ray(),
+            t in 0.1f32..100.,
+            angle in 0.1f32..PI - 0.1,
+            seg_len in 0.5f32..100.,
+            // the line through the segment crosses the ray, but before src or after dst
+            u in prop_oneof![-10f32..-0.01, 1.01f32..10.],
+        ) {
+            let hit = ray.origin + t * ray.dir;
+            let seg_dir = Vec2::from_angle(angle).rotate(ray.dir.normalize()) * seg_len;
+            let seg = segment_through(hit, seg_dir, u);
+            prop_assert_eq!(ray.intersection(&seg), None);
+        }
+
+        #[test]
+        fn prop_parallel_misses(
+            ray in any_ray(),
+            offset in prop_oneof![-100f32..-0.01, 0.01f32..100.],
+            t in -100f32..100.,
+            seg_len in prop_oneof![-100f32..-0.5, 0.5f32..100.],
+            // nearly parallel is treated the same as parallel rather than producing huge t
+            tilt in -1e-7f32..1e-7,
+        ) {
+            let normal = ray.dir.normalize().perp();
+            let src = ray.origin + t * ray.dir + offset * normal;
+            let seg_dir = Vec2::from_angle(tilt).rotate(ray.dir.normalize()) * seg_len;
+            let seg = Segment { src, dst: src + seg_dir };
+            prop_assert_eq!(ray.intersection(&seg), None);
+        }
+
+        #[test]
+        fn prop_collinear_returns_nearest_point(
+            ray in any_ray(),
+            t0 in -100f32..100.,
+            t1 in -100f32..100.,
+        ) {
+            prop_assume!((t1 - t0).abs() > 0.1);
+            let seg = Segment {
+                src: ray.origin + t0 * ray.dir,
+                dst: ray.origin + t1 * ray.dir,
+            };
+            let result = ray.intersection(&seg);
+            if t0.max(t1) < 0. {
+                prop_assert_eq!(result, None);
+            } else {
+                let nearest = ray.origin + t0.min(t1).max(0.) * ray.dir;
+                prop_assert!(result.is_some_and(|r| approx_eq(r, nearest)), "{result:?} != {nearest}");
+            }
+        }
+
+        #[test]
+        fn prop_zero_length_segment(ray in any_ray(), t in -100f32..100., offset in -10f32..10.) {
+            let point = ray.origin + t * ray.dir + offset * ray.dir.normalize().perp();
+            let seg = Segment { src: point, dst: point };
+            let on_ray = t >= 0. && offset.abs() < DISTANCE_EPSILON;
+            prop_assert_eq!(ray.intersection(&seg), on_ray.then_some(point));
+        }
+
+        #[test]
+        fn prop_hit_is_on_segment_and_in_front(
+            ray in any_ray(),
+            sx in -200f32..200., sy in -200f32..200.,
+            dx in -200f32..200., dy in -200f32..200.,
+        ) {
+            let seg = Segment { src: Vec2::new(sx, sy), dst: Vec2::new(dx, dy) };
+            if let Some(hit) = ray.intersection(&seg) {
+                let to_hit = hit - ray.origin;
+                prop_assert!(to_hit.dot(ray.dir) >= -1e-3);
+                let along = seg.parametric_dir();
+                let u = (hit - seg.src).dot(along) / along.length_squared();
+                prop_assert!((-1e-3..=1. + 1e-3).contains(&u));
+                prop_assert!(approx_eq(seg.src + u * along, hit));
+            }
+        }
+    }
+
+    /// a 20x20 box centered on the origin
+    fn enclosing_box() -> Vec<Segment> {
+        rect_segments(Rect::new(-10., -10., 20., 20.)).to_vec()
+    }
+
+    fn contains_approx(polygon: &[Vec2], point: Vec2) -> bool {
+        polygon.iter().any(|p| p.distance(point) < 0.01)
+    }
+
+    #[test]
+    fn test_visibility_polygon_empty() {
+        assert_eq!(visibility_polygon(Vec2::ZERO, &[]), vec![]);
+    }
+
+    #[test]
+    fn test_visibility_polygon_box() {
+        let polygon = visibility_polygon(Vec2::ZERO, &enclosing_box());
+
+        for corner in [
+            Vec2::new(-10., -10.),
+            Vec2::new(10., -10.),
+            Vec2::new(10., 10.),
+            Vec2::new(-10., 10.),
+        ] {
+            assert!(contains_approx(&polygon, corner), "missing {corner}");
+        }
+
+        // every vertex is on the box
+        for p in &polygon {
+            assert!(
+                (p.x.abs() - 10.).abs() < 0.01 || (p.y.abs() - 10.).abs() < 0.01,
+                "{p} not on the box"
+            );
+        }
+
+        // sorted by angle around the origin
+        let angles: Vec<f32> = polygon.iter().map(|p| p.y.atan2(p.x)).collect();
+        assert!(angles.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn test_visibility_polygon_shadow() {
+        let mut segments = enclosing_box();
+        // a small wall in front of the origin, which casts a shadow onto the right side of the box
+        segments.push(Segment {
+            src: Vec2::new(5., -1.),
+            dst: Vec2::new(5., 1.),
+        });
+        let polygon = visibility_polygon(Vec2::ZERO, &segments);
+
+        // the near corners of the wall are visible...
+        assert!(contains_approx(&polygon, Vec2::new(5., -1.)));
+        assert!(contains_approx(&polygon, Vec2::new(5., 1.)));
+        // ...and the rays slipping past them hit the box at the edge of the shadow
+        assert!(contains_approx(&polygon, Vec2::new(10., -2.)));
+        assert!(contains_approx(&polygon, Vec2::new(10., 2.)));
+        // but nothing on the part of the box directly behind the wall is
+        assert!(polygon
+            .iter()
+            .filter(|p| (p.x - 10.).abs() < 0.01)
+            .all(|p| p.y.abs() > 1.99));
+    }
+
+    #[test]
+    fn test_visibility_polygon_thin_obstacle() {
+        let mut segments = enclosing_box();
+        // thinner than the gap between rays of a 360 ray sweep at this distance
+        let sliver = Segment {
+            src: Vec2::new(8., 0.01),
+            dst: Vec2::new(8., 0.05),
+        };
+        segments.push(sliver);
+        let polygon = visibility_polygon(Vec2::ZERO, &segments);
+
+        assert!(contains_approx(&polygon, sliver.src));
+        assert!(contains_approx(&polygon, sliver.dst));
+    }
 }
@@ -0,0 +1,248 @@
+use macroquad::color::{GRAY, RED, WHITE};
+use macroquad::math::Vec2;
+
+use crate::audio::{self, play_sfx};
+use crate::consts::VIRTUAL_WIDTH;
+use crate::context::Context;
+use crate::input::Action;
+use crate::text::{draw_text, Size};
+
+/// vertical distance between the items
+const ITEM_SPACING: f32 = 40.;
+/// how far above its text's baseline an item can be clicked
+const ITEM_ASCENT: f32 = 30.;
+
+/// a vertical list of options navigated with the menu actions or the mouse, wrapping around and
+/// skipping disabled items; scenes describe the items and react to the events
+pub struct Menu<T> {
+    items: Vec<Item<T>>,
+    index: usize,
+    /// where the first item's text is drawn
+    position: Vec2,
+}
+
+struct Item<T> {
+    value: T,
+    /// disabled items are shown grayed out and can't be selected
+    enabled: bool,
+    /// whether left and right change the item's value, like for sliders and cycles
+    adjustable: bool,
+}
+
+/// what happened to the menu this frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MenuEvent<T> {
+    /// the item was picked
+    Confirm(T),
+    /// left (-1) or right (1) was pressed on an adjustable item
+    Adjust(T, i32),
+}
+
+impl<T: Copy + PartialEq> Menu<T> {
+    pub fn new(values: Vec<T>, x: f32, y: f32) -> Self {
+        let items = values
+            .into_iter()
+            .map(|value| Item {
+                value,
+                enabled: true,
+                adjustable: false,
+            })
+            .collect();
+
+        Self {
+            items,
+            index: 0,
+            position: Vec2::new(x, y),
+        }
+    }
+
+    /// enables or disables the item, moving the selection off of it if needed
+    pub fn set_enabled(&mut self, value: T, enabled: bool) {
+        if let Some(item) = self.items.iter_mut().find(|item| item.value == value) {
+            item.enabled = enabled;
+        }
+        if !self.items[self.index].enabled {
+            self.move_selection(1);
+        }
+    }
+
+    /// lets left and right adjust the item
+    pub fn set_adjustable(&mut self, value: T, adjustable: bool) {
+        if let Some(item) = self.items.iter_mut().find(|item| item.value == value) {
+            item.adjustable = adjustable;
+        }
+    }
+
+    /// the item currently highlighted
+    pub fn selected(&self) -> T {
+        self.items[self.index].value
+    }
+
+    /// selects the first enabled item again, for when the menu is shown again
+    pub fn reset(&mut self) {
+        self.index = 0;
+        if !self.items[self.index].enabled {
+            self.move_selection(1);
+        }
+    }
+
+    /// moves the selection by `step` enabled items, wrapping around at either end
+    fn move_selection(&mut self, step: i32) {
+        let len = self.items.len() as i32;
+        let mut index = self.index as i32;
+        for _ in 0..step.abs() {
+            // give up after a full loop in case every item is disabled
+            for _ in 0..len {
+                index = (index + step.signum()).rem_euclid(len);
+                if self.items[index as usize].enabled {
+                    break;
+                }
+            }
+        }
+        self.index = index as usize;
+    }
+
+    /// the enabled item at the position in virtual screen coordinates, rows span the whole width
+    fn item_at(&self, point: Vec2) -> Option<usize> {
+        if point.x < 0. || point.x > VIRTUAL_WIDTH {
+            return None;
+        }
+        let offset = point.y - (self.position.y - ITEM_ASCENT);
+        if offset < 0. {
+            return None;
+        }
+        let index = (offset / ITEM_SPACING) as usize;
+        self.items
+            .get(index)
+            .filter(|item| item.enabled)
+            .map(|_| index)
+    }
+
+    /// handles navigation for the frame, playing the menu sounds
+    pub fn update(&mut self, ctx: &Context) -> Option<MenuEvent<T>> {
+        let previous_index = self.index;
+        if ctx.input.repeated(Action::Up) {
+            self.move_selection(-1);
+        }
+        if ctx.input.repeated(Action::Down) {
+            self.move_selection(1);
+        }
+
+        let pointed_at = ctx
+            .input
+            .pointer()
+            .and_then(|pointer| self.item_at(pointer));
+        if let Some(index) = pointed_at {
+            if ctx.input.pointer_moved() || ctx.input.pointer_pressed() {
+                self.index = index;
+            }
+        }
+
+        if self.index != previous_index {
+            play_sfx(ctx, audio::MENU_MOVE);
+        }
+
+        if ctx.input.pressed(Action::Confirm)
+            || (pointed_at.is_some() && ctx.input.pointer_pressed())
+        {
+            play_sfx(ctx, audio::MENU_SELECT);
+            return Some(MenuEvent::Confirm(self.selected()));
+        }
+
+        if self.items[self.index].adjustable {
+            let step =
+                ctx.input.repeated(Action::Right) as i32 - ctx.input.repeated(Action::Left) as i32;
+            if step != 0 {
+                play_sfx(ctx, audio::MENU_MOVE);
+                return Some(MenuEvent::Adjust(self.selected(), step));
+            }
+        }
+
+        None
+    }
+
+    /// draws every item with the text `label` gives it, highlighting the selected one
+    pub fn draw(&self, ctx: &mut Context, label: impl Fn(&Context, T) -> String) {
+        for (i, item) in self.items.iter().enumerate() {
+            let color = if self.index == i {
+                RED
+            } else if !item.enabled {
+                GRAY
+            } else {
+                WHITE
+            };
+
+            let text = label(ctx, item.value);
+            draw_text(
+                ctx,
+                text.as_str(),
+                self.position.x,
+                self.position.y + (i as f32 * ITEM_SPACING),
+                Size::Medium,
+                color,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn menu() -> Menu<char> {
+        Menu::new(vec!['a', 'b', 'c', 'd'], 40., 200.)
+    }
+
+    #[test]
+    fn test_wraps_around() {
+        let mut menu = menu();
+        menu.move_selection(-1);
+        assert_eq!(menu.selected(), 'd');
+        menu.move_selection(1);
+        assert_eq!(menu.selected(), 'a');
+        menu.move_selection(2);
+        assert_eq!(menu.selected(), 'c');
+    }
+
+    #[test]
+    fn test_skips_disabled_items() {
+        let mut menu = menu();
+        menu.set_enabled('b', false);
+        menu.set_enabled('c', false);
+        menu.move_selection(1);
+        assert_eq!(menu.selected(), 'd');
+        menu.move_selection(-1);
+        assert_eq!(menu.selected(), 'a');
+
+        // disabling the selected item moves off of it
+        menu.set_enabled('a', false);
+        assert_eq!(menu.selected(), 'd');
+        menu.reset();
+        assert_eq!(menu.selected(), 'd');
+    }
+
+    #[test]
+    fn test_all_disabled_does_not_hang() {
+        let mut menu = menu();
+        for value in ['a', 'b', 'c', 'd'] {
+            menu.set_enabled(value, false);
+        }
+        menu.move_selection(1);
+        menu.reset();
+    }
+
+    #[test]
+    fn test_item_at() {
+        let mut menu = menu();
+        assert_eq!(menu.item_at(Vec2::new(100., 200.)), Some(0));
+        assert_eq!(menu.item_at(Vec2::new(100., 175.)), Some(0));
+        assert_eq!(menu.item_at(Vec2::new(100., 240.)), Some(1));
+        assert_eq!(menu.item_at(Vec2::new(100., 320.)), Some(3));
+        assert_eq!(menu.item_at(Vec2::new(100., 160.)), None);
+        assert_eq!(menu.item_at(Vec2::new(100., 400.)), None);
+        assert_eq!(menu.item_at(Vec2::new(-10., 200.)), None);
+
+        menu.set_enabled('b', false);
+        assert_eq!(menu.item_at(Vec2::new(100., 240.)), None);
+    }
+}
@@ -0,0 +1,218 @@
+use std::collections::BTreeMap;
+
+use macroquad::audio::{play_sound, set_sound_volume, stop_sound, PlaySoundParams, Sound};
+
+use crate::settings::{Settings, Volume};
+
+/// played in the main menu and its sub-scenes
+pub const MENU_TRACK: &str = "menu";
+/// played once the player has crashed
+pub const GAME_OVER_TRACK: &str = "game_over";
+/// seconds it takes for one track to fade into the next
+const CROSSFADE_DURATION: f32 = 1.5;
+/// how loud the music is while ducked, e.g. under the pause menu
+const DUCKED_GAIN: f32 = 0.3;
+/// seconds it takes to duck or come back up
+const DUCK_DURATION: f32 = 0.25;
+
+/// loops a music track at a time, crossfading between them, on the music volume bus
+#[derive(Default)]
+pub struct MusicPlayer {
+    tracks: BTreeMap<String, Sound>,
+    mix: Mix,
+}
+
+impl MusicPlayer {
+    /// makes a looping sound from the asset manifest playable by its id
+    pub fn add_track(&mut self, name: String, sound: Sound) {
+        self.tracks.insert(name, sound);
+    }
+
+    /// fades the track in, fading out whatever else is playing; does nothing if it's already
+    /// the track playing, so it carries on between scenes that share it
+    ///
+    /// Fades out to silence if the track isn't a looping sound in the asset manifest
+    pub fn play(&mut self, name: &str) {
+        let Some(sound) = self.tracks.get(name) else {
+            self.stop();
+            return;
+        };
+        if self.mix.play(name) {
+            play_sound(
+                sound,
+                PlaySoundParams {
+                    looped: true,
+                    volume: 0.,
+                },
+            );
+        }
+    }
+
+    /// fades out whatever is playing, for silence
+    pub fn stop(&mut self) {
+        self.mix.stop();
+    }
+
+    /// keeps the music quieter for this frame, needs calling every frame it should stay down
+    pub fn duck(&mut self) {
+        self.mix.ducked = true;
+    }
+
+    /// moves the fades along by `dt` seconds and applies the volume settings, once per frame
+    pub fn update(&mut self, settings: &Settings, dt: f32) {
+        for name in self.mix.update(dt) {
+            stop_sound(&self.tracks[&name]);
+        }
+
+        let gain = settings.gain(Volume::Music);
+        for (name, level) in self.mix.levels() {
+            set_sound_volume(&self.tracks[name], gain * level);
+        }
+    }
+}
+
+/// how loud each playing track is, without any actual sounds so it can be tested
+#[derive(Debug, Default)]
+struct Mix {
+    /// the track fading in or playing, and how far it's faded in, from 0 to 1
+    current: Option<(String, f32)>,
+    /// tracks on their way out, still playing until they're silent
+    fading_out: Vec<(String, f32)>,
+    /// whether to duck this frame
+    ducked: bool,
+    /// how far ducked the music is, from 0 (full volume) to 1 (all the way down)
+    duck: f32,
+}
+
+impl Mix {
+    /// makes the track the current one, returns whether it needs to start playing
+    fn play(&mut self, name: &str) -> bool {
+        if self
+            .current
+            .as_ref()
+            .is_some_and(|(current, _)| current == name)
+        {
+            return false;
+        }
+        self.stop();
+
+        // a track on its way out comes back from where it got to, as it's still playing
+        match self.fading_out.iter().position(|(n, _)| n == name) {
+            Some(i) => {
+                self.current = Some(self.fading_out.remove(i));
+                false
+            }
+            None => {
+                self.current = Some((name.to_string(), 0.));
+                true
+            }
+        }
+    }
+
+    fn stop(&mut self) {
+        if let Some(current) = self.current.take() {
+            self.fading_out.push(current);
+        }
+    }
+
+    /// advances the fades, returns the tracks that have faded out and need stopping
+    fn update(&mut self, dt: f32) -> Vec<String> {
+        let step = dt / CROSSFADE_DURATION;
+        if let Some((_, fade)) = &mut self.current {
+            *fade = (*fade + step).min(1.);
+        }
+
+        for (_, fade) in &mut self.fading_out {
+            *fade -= step;
+        }
+        let (silent, fading_out) = std::mem::take(&mut self.fading_out)
+            .into_iter()
+            .partition(|(_, fade)| *fade <= 0.);
+        self.fading_out = fading_out;
+
+        let duck_step = dt / DUCK_DURATION;
+        self.duck = if self.ducked {
+            (self.duck + duck_step).min(1.)
+        } else {
+            (self.duck - duck_step).max(0.)
+        };
+        self.ducked = false;
+
+        silent.into_iter().map(|(name, _)| name).collect()
+    }
+
+    /// how loud each playing track should be, from 0 to 1, before the volume settings
+    fn levels(&self) -> impl Iterator<Item = (&String, f32)> {
+        let duck = 1. - (1. - DUCKED_GAIN) * self.duck;
+        self.current
+            .iter()
+            .chain(self.fading_out.iter())
+            .map(move |(name, fade)| (name, fade * duck))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(mix: &Mix, name: &str) -> Option<f32> {
+        mix.levels().find(|(n, _)| *n == name).map(|(_, l)| l)
+    }
+
+    #[test]
+    fn test_crossfade() {
+        let mut mix = Mix::default();
+        assert!(mix.play("menu"));
+        mix.update(CROSSFADE_DURATION);
+        assert_eq!(level(&mix, "menu"), Some(1.));
+
+        assert!(mix.play("level"));
+        assert!(mix.update(CROSSFADE_DURATION / 2.).is_empty());
+        assert_eq!(level(&mix, "menu"), Some(0.5));
+        assert_eq!(level(&mix, "level"), Some(0.5));
+
+        assert_eq!(mix.update(CROSSFADE_DURATION / 2.), ["menu"]);
+        assert_eq!(level(&mix, "menu"), None);
+        assert_eq!(level(&mix, "level"), Some(1.));
+    }
+
+    #[test]
+    fn test_same_track_keeps_playing() {
+        let mut mix = Mix::default();
+        mix.play("menu");
+        mix.update(CROSSFADE_DURATION);
+        assert!(!mix.play("menu"));
+        assert_eq!(level(&mix, "menu"), Some(1.));
+    }
+
+    #[test]
+    fn test_track_fading_out_comes_back() {
+        let mut mix = Mix::default();
+        mix.play("menu");
+        mix.update(CROSSFADE_DURATION);
+        mix.play("level");
+        mix.update(CROSSFADE_DURATION / 4.);
+
+        // it's still playing, so it doesn't need to be started again
+        assert!(!mix.play("menu"));
+        assert_eq!(level(&mix, "menu"), Some(0.75));
+        mix.update(CROSSFADE_DURATION / 4.);
+        assert_eq!(level(&mix, "menu"), Some(1.));
+    }
+
+    #[test]
+    fn test_duck_while_requested() {
+        let mut mix = Mix::default();
+        mix.play("level");
+        mix.update(CROSSFADE_DURATION);
+
+        mix.ducked = true;
+        mix.update(DUCK_DURATION);
+        let ducked = level(&mix, "level").unwrap();
+        assert!((ducked - DUCKED_GAIN).abs() < 1e-6, "{ducked}");
+
+        // comes back up once nothing asks for it
+        mix.update(DUCK_DURATION);
+        assert_eq!(level(&mix, "level"), Some(1.));
+    }
+}
@@ -0,0 +1,232 @@
+#[cfg(not(target_family = "wasm"))]
+use crate::dir;
+#[cfg(not(target_family = "wasm"))]
+use macroquad::logging::warn;
+use serde::{Deserialize, Serialize};
+#[cfg(not(target_family = "wasm"))]
+use std::path::PathBuf;
+
+use crate::consts::{FIXED_TIMESTEP, VERSION};
+use crate::level::Level;
+use crate::world::{TickInput, World};
+
+/// a recorded run: since the world is deterministic, its seed and the input for every fixed
+/// update are enough to play it back exactly
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Replay {
+    /// the simulation may change between versions, so replays only play back on the one that
+    /// recorded them
+    game_version: String,
+    /// id of the level that was played
+    pub level_id: String,
+    pub seed: u64,
+    /// the input for each fixed update in order, as how many ticks in a row it was held for and
+    /// the input itself, since it rarely changes from one tick to the next
+    inputs: Vec<(u32, TickInput)>,
+}
+
+#[cfg(not(target_family = "wasm"))]
+const REPLAY_FILE: &str = "replay.ron";
+
+#[cfg(target_family = "wasm")]
+const WASM_REPLAY_KEY: &str = "replay";
+
+impl Replay {
+    /// starts recording a run of the level
+    pub fn new(level: &Level, seed: u64) -> Self {
+        Self {
+            game_version: VERSION.to_string(),
+            level_id: level.id.clone(),
+            seed,
+            inputs: vec![],
+        }
+    }
+
+    /// appends the input for the next fixed update
+    pub fn record(&mut self, input: TickInput) {
+        match self.inputs.last_mut() {
+            Some((ticks, last)) if *last == input => *ticks += 1,
+            _ => self.inputs.push((1, input)),
+        }
+    }
+
+    /// how many fixed updates have been recorded
+    pub fn len(&self) -> usize {
+        self.inputs.iter().map(|(ticks, _)| *ticks as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    /// the input for each fixed update, in order
+    pub fn inputs(&self) -> Vec<TickInput> {
+        self.inputs
+            .iter()
+            .flat_map(|(ticks, input)| std::iter::repeat_n(*input, *ticks as usize))
+            .collect()
+    }
+
+    /// runs the whole replay without rendering, returning the world as it was on the last tick
+    pub fn play_back(&self, level: Level) -> World {
+        let mut world = World::new(level, self.seed);
+        for input in self.inputs() {
+            world.step(&input, FIXED_TIMESTEP);
+        }
+        world
+    }
+
+    /// loads the most recently saved replay, if there is one this version of the game can play
+    pub fn load_last() -> Option<Self> {
+        #[cfg(target_family = "wasm")]
+        let replay_str = {
+            let storage = &mut quad_storage::STORAGE.lock().unwrap();
+            storage.get(WASM_REPLAY_KEY)?
+        };
+
+        #[cfg(not(target_family = "wasm"))]
+        let replay_str = std::fs::read_to_string(Self::determine_replay_path()).ok()?;
+
+        ron::from_str::<Replay>(&replay_str)
+            .ok()
+            .filter(|replay| replay.game_version == VERSION)
+    }
+
+    /// writes the replay to local storage, replacing the last one
+    #[cfg(target_family = "wasm")]
+    pub fn save_last(&self) {
+        let storage = &mut quad_storage::STORAGE.lock().unwrap();
+        storage.set(WASM_REPLAY_KEY, &self.to_ron_string());
+    }
+
+    /// writes the replay to disk, replacing the last one; a replay is only nice to have, so one
+    /// that can't be written, e.g. with the disk full, is logged and the game carries on
+    #[cfg(not(target_family = "wasm"))]
+    pub fn save_last(&self) {
+        let replay_path = Self::determine_replay_path();
+        let written = replay_path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::write(&replay_path, self.to_ron_string()));
+        if let Err(e) = written {
+            warn!("couldn't save the replay to {}: {e}", replay_path.display());
+        }
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    fn determine_replay_path() -> PathBuf {
+        let project_dirs = dir::project_dirs();
+        let mut replay_path = PathBuf::from(project_dirs.data_local_dir());
+        replay_path.push(REPLAY_FILE);
+        replay_path
+    }
+
+    /// returns the replay in RON format; not pretty, since there can be thousands of inputs
+    fn to_ron_string(&self) -> String {
+        ron::to_string(self).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::{Curve, Range};
+    use macroquad::math::Vec2;
+
+    fn test_level() -> Level {
+        Level {
+            id: "test".to_string(),
+            name: "Test".to_string(),
+            duration: 30.,
+            spawn_interval: Curve {
+                start: 0.5,
+                end: 0.2,
+            },
+            crystal_spawn_interval: 2.,
+            asteroid_width: Range { min: 20., max: 80. },
+            asteroid_height: Range {
+                min: 20.,
+                max: 200.,
+            },
+            asteroid_speed: Range {
+                min: 150.,
+                max: 400.,
+            },
+            crystal_quota: 1_000,
+            walls: vec![],
+            music: None,
+            assets: Default::default(),
+        }
+    }
+
+    /// weaves up and down with a partly pushed stick while firing until crashing, recording every
+    /// tick
+    fn record_run(level: &Level, seed: u64) -> (Replay, World) {
+        let mut replay = Replay::new(level, seed);
+        let mut world = World::new(level.clone(), seed);
+        for tick in 0..(120 * 120) {
+            let y = match tick % 300 {
+                0..100 => -0.8,
+                200.. => 0.8,
+                _ => 0.,
+            };
+            let x = ((tick % 700) as f32 / 350. - 1.) * 0.3;
+            let input = TickInput {
+                movement: Vec2::new(x, y),
+                fire: tick % 90 < 60,
+            };
+            replay.record(input);
+            world.step(&input, FIXED_TIMESTEP);
+            if world.died_at.is_some() {
+                break;
+            }
+        }
+        (replay, world)
+    }
+
+    #[test]
+    fn test_record_compresses_held_input() {
+        let mut replay = Replay::new(&test_level(), 0);
+        let fire = TickInput {
+            fire: true,
+            ..Default::default()
+        };
+        for _ in 0..10 {
+            replay.record(TickInput::default());
+        }
+        for _ in 0..5 {
+            replay.record(fire);
+        }
+        replay.record(TickInput::default());
+
+        assert_eq!(replay.inputs.len(), 3);
+        assert_eq!(replay.len(), 16);
+        let inputs = replay.inputs();
+        assert_eq!(inputs[9], TickInput::default());
+        assert_eq!(inputs[10], fire);
+        assert_eq!(inputs[15], TickInput::default());
+    }
+
+    #[test]
+    fn test_ron_round_trip() {
+        let (replay, _) = record_run(&test_level(), 11);
+        let parsed: Replay = ron::from_str(&replay.to_ron_string()).unwrap();
+        assert_eq!(parsed, replay);
+    }
+
+    #[test]
+    fn test_play_back_matches_recorded_run() {
+        let level = test_level();
+        for seed in [1, 2, 3] {
+            let (replay, recorded) = record_run(&level, seed);
+            assert!(recorded.died_at.is_some(), "seed {seed} should crash");
+
+            let parsed: Replay = ron::from_str(&replay.to_ron_string()).unwrap();
+            let played = parsed.play_back(level.clone());
+            assert_eq!(played.died_at, recorded.died_at);
+            assert_eq!(played.score, recorded.score);
+            assert_eq!(played.player_position, recorded.player_position);
+            assert_eq!(played.asteroids.len(), recorded.asteroids.len());
+        }
+    }
+}
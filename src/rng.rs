@@ -0,0 +1,67 @@
+use macroquad::miniquad::date;
+
+/// a small seedable random number generator (SplitMix64)
+///
+/// Unlike macroquad's global `rand()`, each instance owns its state, so a run can be reproduced
+/// exactly by creating it again with the same seed.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// a seed that's different on each launch, based on the current time
+    pub fn seed_from_time() -> u64 {
+        (date::now() * 1000.) as u64
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// a random value from 0 (inclusive) to 1 (exclusive)
+    pub fn ratio(&mut self) -> f32 {
+        // the top 24 bits fit exactly into an f32's mantissa
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_sequence() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+    }
+
+    #[test]
+    fn test_different_seed_different_sequence() {
+        let mut a = Rng::new(1);
+        let mut b = Rng::new(2);
+        let a_values: Vec<u64> = (0..10).map(|_| a.next_u64()).collect();
+        let b_values: Vec<u64> = (0..10).map(|_| b.next_u64()).collect();
+        assert_ne!(a_values, b_values);
+    }
+
+    #[test]
+    fn test_ratio_range() {
+        let mut rng = Rng::new(0);
+        for _ in 0..10_000 {
+            let r = rng.ratio();
+            assert!((0. ..1.).contains(&r), "{r}");
+        }
+    }
+}
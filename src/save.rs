@@ -1,15 +1,30 @@
 #[cfg(not(target_family = "wasm"))]
 use crate::dir;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 #[cfg(not(target_family = "wasm"))]
 use std::path::PathBuf;
 
 use crate::consts::VERSION;
+use crate::level::Level;
 
 /// game completion progress
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Save {
     game_version: String,
+    /// progress for each level that's been played, keyed by the level's id
+    #[serde(default)]
+    levels: BTreeMap<String, LevelProgress>,
+}
+
+/// the player's record for a single level
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct LevelProgress {
+    pub completed: bool,
+    /// fewest seconds it took to complete the level
+    pub best_time: Option<f64>,
+    /// most warp crystals collected in a single run of the level
+    pub crystals: u32,
 }
 
 #[cfg(not(target_family = "wasm"))]
@@ -22,6 +37,7 @@ impl Default for Save {
     fn default() -> Self {
         Self {
             game_version: VERSION.to_string(),
+            levels: BTreeMap::new(),
         }
     }
 }
@@ -39,6 +55,39 @@ impl Save {
         save
     }
 
+    /// the player's record for the level, if they've played it
+    pub fn level_progress(&self, level: &Level) -> Option<&LevelProgress> {
+        self.levels.get(&level.id)
+    }
+
+    /// the first level is always playable, and each level after it is unlocked by completing the
+    /// one before it
+    pub fn is_level_unlocked(&self, levels: &[Level], index: usize) -> bool {
+        index == 0
+            || levels.get(index - 1).is_some_and(|previous| {
+                self.level_progress(previous)
+                    .is_some_and(|progress| progress.completed)
+            })
+    }
+
+    /// records a run of the level that didn't complete it, and saves
+    pub fn record_attempt(&mut self, level: &Level, crystals: u32) {
+        let progress = self.levels.entry(level.id.clone()).or_default();
+        progress.crystals = progress.crystals.max(crystals);
+
+        self.save();
+    }
+
+    /// records a completed run of the level, and saves
+    pub fn record_completion(&mut self, level: &Level, time: f64, crystals: u32) {
+        let progress = self.levels.entry(level.id.clone()).or_default();
+        progress.completed = true;
+        progress.best_time = Some(progress.best_time.map_or(time, |best| best.min(time)));
+        progress.crystals = progress.crystals.max(crystals);
+
+        self.save();
+    }
+
     #[cfg(not(target_family = "wasm"))]
     fn load_desktop() -> Self {
         let save_path = Self::determine_save_path();
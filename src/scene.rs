@@ -2,19 +2,46 @@
 // not sure if there's a better way to do this...
 #[derive(Clone, Debug)]
 pub enum EScene {
-    Gameplay,
+    /// playing the level at the index in `Context::levels`
+    Gameplay(usize),
     MainMenu,
+    /// watching the most recently saved replay
+    Replay,
+    /// listing assets that couldn't be loaded, before carrying on to the scene
+    AssetErrors(Box<EScene>),
 }
 
 use crate::context::Context;
+use crate::input::Action;
 
+pub mod asset_errors;
+pub mod controls;
 pub mod credits;
+pub mod game_over;
 pub mod gameplay;
+pub mod level_complete;
+pub mod level_select;
+pub mod loading;
 pub mod main_menu;
 pub mod pause;
 pub mod settings;
+pub mod win;
 
 pub trait Scene {
+    /// called once per rendered frame, for menus and anything reacting to pressed input
     fn update(&mut self, ctx: &mut Context);
+    /// called zero or more times per frame to advance the simulation by exactly `dt` seconds
+    fn fixed_update(&mut self, _ctx: &mut Context, _dt: f32) {}
     fn draw(&mut self, ctx: &mut Context);
 }
+
+/// tells the player how to leave a screen that either confirm or cancel closes, with whatever
+/// they've bound to them
+pub fn return_hint(ctx: &Context) -> String {
+    let bindings = ctx.settings.bindings();
+    format!(
+        "Press {} or {} to return",
+        bindings.describe(Action::Confirm),
+        bindings.describe(Action::Cancel)
+    )
+}
@@ -0,0 +1,112 @@
+use macroquad::color::{RED, WHITE};
+
+use super::EScene;
+use super::Scene;
+use crate::consts::TITLE_Y_INSET;
+use crate::consts::VIRTUAL_HEIGHT;
+use crate::consts::X_INSET;
+use crate::menu::{Menu, MenuEvent};
+use crate::text::Size;
+use crate::{context::Context, text::draw_text};
+
+/// space between each listed error
+const LINE_SPACING: f32 = 26.;
+/// the most errors listed, so they don't run into the menu
+const MAX_LINES: usize = 12;
+
+/// shown after loading when assets couldn't be, listing what went wrong and where they were
+/// looked for, before carrying on with placeholders in their place
+pub struct AssetErrors {
+    menu: Menu<MenuOption>,
+    /// the scene the loading was for
+    next: EScene,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum MenuOption {
+    Continue,
+    #[cfg(not(target_family = "wasm"))]
+    Quit,
+}
+
+impl AssetErrors {
+    pub fn new(_ctx: &Context, next: EScene) -> Self {
+        let menu_options = vec![
+            MenuOption::Continue,
+            #[cfg(not(target_family = "wasm"))]
+            MenuOption::Quit,
+        ];
+
+        Self {
+            menu: Menu::new(menu_options, X_INSET, VIRTUAL_HEIGHT - 120.),
+            next,
+        }
+    }
+
+    fn text_for_menu_option(menu_option: MenuOption) -> &'static str {
+        match menu_option {
+            MenuOption::Continue => "Continue Anyway",
+            #[cfg(not(target_family = "wasm"))]
+            MenuOption::Quit => "Quit",
+        }
+    }
+}
+
+impl Scene for AssetErrors {
+    fn update(&mut self, ctx: &mut Context) {
+        if let Some(MenuEvent::Confirm(menu_option)) = self.menu.update(ctx) {
+            match menu_option {
+                MenuOption::Continue => {
+                    // they've been seen, so the next loading screen only lists its own
+                    ctx.asset_errors.clear();
+                    ctx.switch_scene_to = Some(self.next.clone());
+                }
+                #[cfg(not(target_family = "wasm"))]
+                MenuOption::Quit => {
+                    ctx.request_quit = true;
+                }
+            }
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut Context) {
+        draw_text(
+            ctx,
+            "Some assets failed to load",
+            X_INSET,
+            TITLE_Y_INSET,
+            Size::Large,
+            RED,
+        );
+
+        draw_text(
+            ctx,
+            format!("Assets dir: {}", ctx.assets_path.display()).as_str(),
+            X_INSET,
+            170.,
+            Size::Small,
+            WHITE,
+        );
+
+        let lines: Vec<String> = ctx.asset_errors.iter().map(|e| e.to_string()).collect();
+        let mut y = 210.;
+        for line in lines.iter().take(MAX_LINES) {
+            draw_text(ctx, line, X_INSET, y, Size::Small, WHITE);
+            y += LINE_SPACING;
+        }
+        if lines.len() > MAX_LINES {
+            draw_text(
+                ctx,
+                format!("...and {} more", lines.len() - MAX_LINES).as_str(),
+                X_INSET,
+                y,
+                Size::Small,
+                WHITE,
+            );
+        }
+
+        self.menu.draw(ctx, |_ctx, menu_option| {
+            Self::text_for_menu_option(menu_option).to_string()
+        });
+    }
+}
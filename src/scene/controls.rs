@@ -0,0 +1,134 @@
+use macroquad::color::WHITE;
+use macroquad::input::{get_last_key_pressed, is_key_pressed, KeyCode};
+
+use super::Scene;
+use crate::audio::{self, play_sfx};
+use crate::consts::TITLE_Y_INSET;
+use crate::consts::X_INSET;
+use crate::input::Action;
+use crate::menu::{Menu, MenuEvent};
+use crate::text::Size;
+use crate::{context::Context, text::draw_text};
+
+/// sub-scene for rebinding the keys and buttons for each action
+pub struct Controls {
+    pub active: bool,
+    menu: Menu<MenuOption>,
+    /// the action waiting for a key or button to be pressed to bind to it, if any
+    rebinding: Option<Action>,
+    /// the action that already had the last key or button pressed while rebinding
+    taken_by: Option<Action>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum MenuOption {
+    Action(Action),
+    RestoreDefaults,
+    Back,
+}
+
+/// stops waiting for a new binding; can't be bound itself, so the player can't get stuck
+const CANCEL_REBINDING_KEY: KeyCode = KeyCode::Escape;
+
+impl Controls {
+    pub fn new(_ctx: &Context) -> Self {
+        let mut menu_options: Vec<MenuOption> =
+            Action::ALL.into_iter().map(MenuOption::Action).collect();
+        menu_options.push(MenuOption::RestoreDefaults);
+        menu_options.push(MenuOption::Back);
+
+        Self {
+            menu: Menu::new(menu_options, X_INSET, 200.),
+            active: false,
+            rebinding: None,
+            taken_by: None,
+        }
+    }
+
+    fn text_for_menu_option(&self, ctx: &Context, menu_option: MenuOption) -> String {
+        match menu_option {
+            MenuOption::Action(action) => {
+                if self.rebinding == Some(action) {
+                    match self.taken_by {
+                        Some(other) => format!(
+                            "{action:?}: that's used for {other:?}, press another (Esc to cancel)"
+                        ),
+                        None => format!("{action:?}: press a key or button (Esc to cancel)"),
+                    }
+                } else {
+                    format!("{action:?}: {}", ctx.settings.bindings().describe(action))
+                }
+            }
+            MenuOption::RestoreDefaults => "Restore Defaults".to_string(),
+            MenuOption::Back => "Back".to_string(),
+        }
+    }
+
+    /// binds the first key or gamepad button pressed to the action being rebound
+    fn update_rebinding(&mut self, ctx: &mut Context, action: Action) {
+        if is_key_pressed(CANCEL_REBINDING_KEY) {
+            self.rebinding = None;
+            self.taken_by = None;
+            play_sfx(ctx, audio::MENU_CANCEL);
+            return;
+        }
+
+        // one key or button triggering two actions would make menus, this one included, misbehave
+        let bound = if let Some(key_code) = get_last_key_pressed() {
+            ctx.settings.bind_key(action, key_code)
+        } else if let Some(button) = ctx.gamepads.all().find_map(|g| g.all_just_pressed().next()) {
+            ctx.settings.bind_button(action, button)
+        } else {
+            return;
+        };
+
+        match bound {
+            Ok(()) => {
+                self.rebinding = None;
+                self.taken_by = None;
+                play_sfx(ctx, audio::MENU_SELECT);
+            }
+            Err(other) => {
+                self.taken_by = Some(other);
+                play_sfx(ctx, audio::MENU_CANCEL);
+            }
+        }
+    }
+}
+
+impl Scene for Controls {
+    fn update(&mut self, ctx: &mut Context) {
+        if let Some(action) = self.rebinding {
+            self.update_rebinding(ctx, action);
+            return;
+        }
+
+        if ctx.input.pressed(Action::Cancel) {
+            self.active = false;
+            play_sfx(ctx, audio::MENU_CANCEL);
+            return;
+        }
+
+        if let Some(MenuEvent::Confirm(menu_option)) = self.menu.update(ctx) {
+            match menu_option {
+                MenuOption::Action(action) => {
+                    self.rebinding = Some(action);
+                }
+                MenuOption::RestoreDefaults => {
+                    ctx.settings.reset_bindings();
+                }
+                MenuOption::Back => {
+                    self.active = false;
+                }
+            }
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut Context) {
+        draw_text(ctx, "Controls", X_INSET, TITLE_Y_INSET, Size::Large, WHITE);
+
+        self.menu.draw(ctx, |ctx, menu_option| {
+            self.text_for_menu_option(ctx, menu_option)
+        });
+    }
+}
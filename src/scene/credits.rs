@@ -1,11 +1,10 @@
 use macroquad::color::{RED, WHITE};
 
-use super::Scene;
-use crate::audio::play_sfx;
+use super::{return_hint, Scene};
+use crate::audio::{self, play_sfx};
 use crate::consts::TITLE_Y_INSET;
 use crate::consts::VIRTUAL_HEIGHT;
 use crate::consts::X_INSET;
-use crate::input::action_pressed;
 use crate::input::Action;
 use crate::text::Size;
 use crate::{context::Context, text::draw_text};
@@ -22,10 +21,11 @@ impl Credits {
 
 impl Scene for Credits {
     fn update(&mut self, ctx: &mut Context) {
-        if action_pressed(Action::Cancel, &ctx.gamepads)
-            || action_pressed(Action::Confirm, &ctx.gamepads)
+        if ctx.input.pressed(Action::Cancel)
+            || ctx.input.pressed(Action::Confirm)
+            || ctx.input.pointer_pressed()
         {
-            play_sfx(ctx, &ctx.audio.sfx.menu_cancel);
+            play_sfx(ctx, audio::MENU_CANCEL);
             self.active = false;
         }
     }
@@ -37,7 +37,7 @@ impl Scene for Credits {
 
         draw_text(
             ctx,
-            "Press Z or X to return",
+            &return_hint(ctx),
             X_INSET,
             VIRTUAL_HEIGHT - 120.,
             Size::Medium,
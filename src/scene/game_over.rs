@@ -0,0 +1,118 @@
+use macroquad::color::WHITE;
+
+use super::EScene;
+use super::Scene;
+use crate::consts::TITLE_Y_INSET;
+use crate::consts::VIRTUAL_HEIGHT;
+use crate::consts::X_INSET;
+use crate::menu::{Menu, MenuEvent};
+use crate::text::Size;
+use crate::{context::Context, text::draw_text};
+
+/// sub-scene rendered when the player crashes, showing how the run went
+pub struct GameOver {
+    pub active: bool,
+    menu: Menu<MenuOption>,
+    /// index of the level that was being played, to retry it
+    level_index: usize,
+    /// how long the run lasted, in seconds
+    time_survived: f64,
+    /// how many asteroids the player made it past
+    score: u32,
+    /// what the run's random number generator was seeded with, to be able to reproduce it
+    seed: u64,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum MenuOption {
+    Retry,
+    WatchReplay,
+    MainMenu,
+}
+
+impl GameOver {
+    pub fn new(_ctx: &mut Context, level_index: usize) -> Self {
+        let menu_options = vec![
+            MenuOption::Retry,
+            MenuOption::WatchReplay,
+            MenuOption::MainMenu,
+        ];
+
+        Self {
+            menu: Menu::new(menu_options, X_INSET, 320.),
+            level_index,
+            active: false,
+            time_survived: 0.,
+            score: 0,
+            seed: 0,
+        }
+    }
+
+    /// activates the sub-scene with the results of the run
+    pub fn show(&mut self, time_survived: f64, score: u32, seed: u64) {
+        self.active = true;
+        self.menu.reset();
+        self.time_survived = time_survived;
+        self.score = score;
+        self.seed = seed;
+    }
+
+    fn text_for_menu_option(menu_option: MenuOption) -> &'static str {
+        match menu_option {
+            MenuOption::Retry => "Retry",
+            MenuOption::WatchReplay => "Watch Replay",
+            MenuOption::MainMenu => "Return to Main Menu",
+        }
+    }
+}
+
+impl Scene for GameOver {
+    fn update(&mut self, ctx: &mut Context) {
+        if let Some(MenuEvent::Confirm(menu_option)) = self.menu.update(ctx) {
+            match menu_option {
+                MenuOption::Retry => {
+                    ctx.switch_scene_to = Some(EScene::Gameplay(self.level_index));
+                }
+                MenuOption::WatchReplay => {
+                    ctx.switch_scene_to = Some(EScene::Replay);
+                }
+                MenuOption::MainMenu => {
+                    ctx.switch_scene_to = Some(EScene::MainMenu);
+                }
+            }
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut Context) {
+        draw_text(ctx, "Game Over", X_INSET, TITLE_Y_INSET, Size::Large, WHITE);
+
+        draw_text(
+            ctx,
+            format!("Time: {:.1}s", self.time_survived).as_str(),
+            X_INSET,
+            200.,
+            Size::Medium,
+            WHITE,
+        );
+        draw_text(
+            ctx,
+            format!("Asteroids dodged: {}", self.score).as_str(),
+            X_INSET,
+            240.,
+            Size::Medium,
+            WHITE,
+        );
+        draw_text(
+            ctx,
+            format!("Seed: {}", self.seed).as_str(),
+            X_INSET,
+            VIRTUAL_HEIGHT - 40.,
+            Size::Small,
+            WHITE,
+        );
+
+        self.menu.draw(ctx, |_ctx, menu_option| {
+            Self::text_for_menu_option(menu_option).to_string()
+        });
+    }
+}
@@ -1,349 +1,370 @@
-use std::f32::consts::PI;
-
+use super::game_over::GameOver;
+use super::level_complete::LevelComplete;
 use super::pause::Pause;
+use super::win::Win;
 use super::Scene;
-use crate::audio::play_sfx;
+use crate::audio::{self, play_sfx};
 use crate::consts::VIRTUAL_HEIGHT;
 use crate::consts::VIRTUAL_WIDTH;
 use crate::context::Context;
-use crate::input::action_down;
-use crate::input::action_pressed;
+use crate::input::touch::TouchControls;
 use crate::input::Action;
-use crate::math::Ray;
-use crate::math::Segment;
+use crate::light::draw_light;
+use crate::math::rect_segments;
+use crate::math::visibility_polygon;
+use crate::music::GAME_OVER_TRACK;
+use crate::replay::Replay;
+use crate::rng::Rng;
+use crate::text::draw_text;
+use crate::text::Size;
+use crate::world::TickInput;
+use crate::world::World;
+use crate::world::WorldEvent;
+use crate::world::PLAYER_RADIUS;
+use crate::world::WARP_ACCELERATION;
 use macroquad::color::*;
-use macroquad::math::Circle;
 use macroquad::math::Rect;
 use macroquad::math::Vec2;
-use macroquad::rand::rand;
 use macroquad::shapes::draw_circle;
+use macroquad::shapes::draw_circle_lines;
 use macroquad::shapes::draw_line;
+use macroquad::shapes::draw_poly;
+use macroquad::shapes::draw_rectangle;
 use macroquad::time::get_frame_time;
-use macroquad::time::get_time;
 
+/// glue between the simulation in `World` and macroquad's input, rendering and sub-scenes
 pub struct Gameplay {
     pause_subscene: Pause,
-
-    player_position: Vec2,
-    walls: Vec<Segment>,
-    asteroids: Vec<Asteroid>,
-    last_spawn_asteroid_time: f64,
-    bullets: Vec<Bullet>,
+    game_over_subscene: GameOver,
+    level_complete_subscene: LevelComplete,
+    win_subscene: Win,
+
+    /// how many gamepads were connected last frame, to notice one being unplugged
+    gamepads_connected: usize,
+    /// index of the level being played in `Context::levels`
+    level_index: usize,
+    /// what the world's random number generator was seeded with, to be able to replay the run
+    seed: u64,
+    /// only stepped by fixed updates, so its clock stops while paused
+    world: World,
+    /// every input of the run so far, or the run being watched
+    replay: Replay,
+    /// the inputs still to play back, if watching a replay instead of playing
+    playback: Option<std::vec::IntoIter<TickInput>>,
+    touch_controls: TouchControls,
 }
 
-const MOVEMENT_SPEED: f32 = 300.;
-const BULLET_RADIUS: f32 = 5.;
+#[cfg(not(target_family = "wasm"))]
+const SEED_ENV_VAR: &str = "SEED";
 const BULLET_COLOR: Color = GREEN;
-const BULLET_MOVEMENT_SPEED: f32 = 200.;
-const PLAYER_RADIUS: f32 = 5.;
+/// distance from the player at which their light has faded out completely
+const LIGHT_RADIUS: f32 = 600.;
+const LIGHT_COLOR: Color = Color::new(1.00, 0.95, 0.75, 0.6);
+const ASTEROID_COLOR: Color = Color::new(0.35, 0.28, 0.22, 1.00);
+/// how long the scene stays frozen on the explosion before showing the game over menu
+const DEATH_FREEZE_DURATION: f64 = 1.5;
+/// how large the explosion grows by the end of the freeze
+const EXPLOSION_RADIUS: f32 = 80.;
+const EXPLOSION_COLOR: Color = ORANGE;
+const CRYSTAL_COLOR: Color = SKYBLUE;
+/// how long the warp-out animation lasts before showing the results
+const WARP_DURATION: f64 = 2.;
+/// how long the light streaks behind the player get per pixel per second of speed
+const WARP_STREAK_FACTOR: f32 = 0.15;
+/// a frame taking at least this many seconds means the window was most likely hidden or
+/// minimized (browsers stop rendering background tabs), so the game pauses itself
+///
+/// This is not focus-loss detection: macroquad doesn't report focus changes, so on desktop a
+/// window that's still visible keeps rendering after alt-tabbing away and won't pause
+const AUTO_PAUSE_FRAME_TIME: f32 = 0.5;
 
-struct Bullet {
-    circle: Circle,
-}
+impl Scene for Gameplay {
+    fn update(&mut self, ctx: &mut Context) {
+        if self.game_over_subscene.active {
+            self.game_over_subscene.update(ctx);
+            return;
+        }
 
-struct Asteroid {
-    rect: Rect,
-}
+        if self.level_complete_subscene.active {
+            self.level_complete_subscene.update(ctx);
+            return;
+        }
 
-impl Asteroid {
-    fn to_segments(&self) -> Vec<Segment> {
-        let r = self.rect;
-        vec![
-            Segment {
-                src: Vec2::new(r.x, r.y),
-                dst: Vec2::new(r.x + r.w, r.y),
-            },
-            Segment {
-                src: Vec2::new(r.x, r.y),
-                dst: Vec2::new(r.x, r.y + r.h),
-            },
-            Segment {
-                src: Vec2::new(r.x, r.y + r.h),
-                dst: Vec2::new(r.x + r.w, r.y + r.h),
-            },
-            Segment {
-                src: Vec2::new(r.x + r.w, r.y),
-                dst: Vec2::new(r.x + r.w, r.y + r.h),
-            },
-        ]
-    }
-}
+        if self.win_subscene.active {
+            self.win_subscene.update(ctx);
+            return;
+        }
+
+        if self.world.died_at.is_some() || self.world.warped_at.is_some() {
+            return;
+        }
+
+        let gamepads_connected = ctx.gamepads.all().count();
+        let gamepad_disconnected = gamepads_connected < self.gamepads_connected;
+        self.gamepads_connected = gamepads_connected;
+        // the first frame's time includes loading the scene, so wait for the run to start
+        let window_hidden = self.world.clock > 0. && get_frame_time() >= AUTO_PAUSE_FRAME_TIME;
 
-impl Scene for Gameplay {
-    fn update(&mut self, ctx: &mut Context) {
         if self.pause_subscene.active {
+            ctx.music.duck();
             self.pause_subscene.update(ctx);
-            // return; // TODO
-        } else if action_pressed(Action::Pause, &ctx.gamepads) {
+            return;
+        }
+
+        self.touch_controls.update(ctx.input.touches());
+        if ctx.input.pressed(Action::Pause) || self.touch_controls.pause_pressed() {
+            self.pause_subscene.active = true;
+            play_sfx(ctx, audio::MENU_SELECT);
+        } else if gamepad_disconnected || window_hidden {
+            // the player can't be playing right now, so don't let them lose the run
             self.pause_subscene.active = true;
-            play_sfx(ctx, &ctx.audio.sfx.menu_select);
         }
+    }
 
-        self.player_movement(ctx);
-        self.player_attack(ctx);
-        self.asteroid_movement();
+    fn fixed_update(&mut self, ctx: &mut Context, dt: f32) {
+        if self.game_over_subscene.active
+            || self.level_complete_subscene.active
+            || self.win_subscene.active
+            || self.pause_subscene.active
+        {
+            return;
+        }
 
-        let player_circle = Circle::new(
-            self.player_position.x,
-            self.player_position.y,
-            PLAYER_RADIUS,
-        );
-        // check for collisions
-        for a in &self.asteroids {
-            if player_circle.overlaps_rect(&a.rect) {
-                // TODO: Handle the collision
-                println!("collision");
+        let input = match &mut self.playback {
+            // past the end of the recording, the run is over and input doesn't matter
+            Some(playback) => playback.next().unwrap_or_default(),
+            None => {
+                let touch_movement = self.touch_controls.movement();
+                let input = TickInput {
+                    movement: if touch_movement != Vec2::ZERO {
+                        touch_movement
+                    } else {
+                        ctx.input.movement()
+                    },
+                    fire: ctx.input.down(Action::Fire) || self.touch_controls.fire(),
+                };
+                if self.world.died_at.is_none() && self.world.warped_at.is_none() {
+                    self.replay.record(input);
+                }
+                input
             }
+        };
+        self.world.step(&input, dt);
+        for event in &self.world.events {
+            play_event_sfx(ctx, event);
         }
 
-        let elapsed = get_time();
-        if elapsed > self.last_spawn_asteroid_time + 1. {
-            self.spawn_asteroid();
-            self.last_spawn_asteroid_time = elapsed;
+        // watching a replay doesn't count towards the player's records
+        let recording = self.playback.is_none();
+        let w = &self.world;
+        if let Some(warped_at) = w.warped_at {
+            if w.clock - warped_at > WARP_DURATION {
+                if recording {
+                    ctx.save
+                        .record_completion(&w.level, w.run_time, w.crystals_collected);
+                    self.replay.save_last();
+                }
+                if self.level_index + 1 == ctx.levels.len() {
+                    self.win_subscene.active = true;
+                } else {
+                    self.level_complete_subscene
+                        .show(w.run_time, w.crystals_collected);
+                }
+            }
+        } else if let Some(died_at) = w.died_at {
+            // freeze on the explosion for a moment before showing the results
+            if w.clock > died_at + DEATH_FREEZE_DURATION {
+                if recording {
+                    ctx.save.record_attempt(&w.level, w.crystals_collected);
+                    self.replay.save_last();
+                }
+                ctx.music.play(GAME_OVER_TRACK);
+                self.game_over_subscene.show(w.run_time, w.score, self.seed);
+            }
         }
     }
 
     fn draw(&mut self, ctx: &mut Context) {
-        if self.pause_subscene.active {
+        if self.game_over_subscene.active {
+            self.game_over_subscene.draw(ctx);
+        } else if self.level_complete_subscene.active {
+            self.level_complete_subscene.draw(ctx);
+        } else if self.win_subscene.active {
+            self.win_subscene.draw(ctx);
+        } else if self.pause_subscene.active {
             self.pause_subscene.draw(ctx);
         } else {
-            self._draw_scene();
+            self._draw_scene(ctx);
         }
     }
 }
 
 impl Gameplay {
-    pub async fn new(ctx: &mut Context) -> Self {
-        let pause_subscene = Pause::new(ctx);
-        let player_position = Vec2::new(300., 300.);
-
-        let asteroids = vec![Asteroid {
-            rect: Rect::new(300., 600., 30., 30.),
-        }];
-
-        let walls = vec![
-            // outer walls
-            // Segment {
-            //     // Left
-            //     src: Vec2::new(0., 0.),
-            //     dst: Vec2::new(0., VIRTUAL_HEIGHT),
-            // },
-            Segment {
-                // Top
-                src: Vec2::new(0., 0.),
-                dst: Vec2::new(VIRTUAL_WIDTH, 0.),
-            },
-            // Segment {
-            //     // Right
-            //     src: Vec2::new(VIRTUAL_WIDTH, 0.),
-            //     dst: Vec2::new(VIRTUAL_WIDTH, VIRTUAL_HEIGHT),
-            // },
-            Segment {
-                // Bottom
-                src: Vec2::new(0., VIRTUAL_HEIGHT),
-                dst: Vec2::new(VIRTUAL_WIDTH, VIRTUAL_HEIGHT),
-            },
-            //     // inner obstacles
-            //     Segment {
-            //         src: Vec2::new(0., 100.),
-            //         dst: Vec2::new(100., 100.),
-            //     },
-            //     Segment {
-            //         src: Vec2::new(100., 0.),
-            //         dst: Vec2::new(100., 100.),
-            //     },
-            //     Segment {
-            //         src: Vec2::new(300., 100.),
-            //         dst: Vec2::new(400., 100.),
-            //     },
-            //     Segment {
-            //         src: Vec2::new(400., 300.),
-            //         dst: Vec2::new(450., 400.),
-            //     },
-            //     Segment {
-            //         src: Vec2::new(500., 300.),
-            //         dst: Vec2::new(590., 200.),
-            //     },
-        ];
-
-        // TODO: How to seed? It's giving same results every time.
-        // let gen_uniform = || rand::rand() as f32 / u32::MAX as f32;
-        // for _ in 0..5 {
-        //     walls.push(Segment {
-        //         src: Vec2::new(
-        //             gen_uniform() * VIRTUAL_WIDTH,
-        //             gen_uniform() * VIRTUAL_HEIGHT,
-        //         ),
-        //         dst: Vec2::new(
-        //             gen_uniform() * VIRTUAL_WIDTH,
-        //             gen_uniform() * VIRTUAL_HEIGHT,
-        //         ),
-        //     })
-        // }
-
-        Self {
-            pause_subscene,
-            player_position,
-            walls,
-            asteroids,
-            last_spawn_asteroid_time: -f64::INFINITY,
-            bullets: vec![],
-        }
+    /// starts the level with a random seed, so each run is different
+    ///
+    /// On desktop, setting the `SEED` env var overrides it, to reproduce a run from the seed
+    /// shown on the game over screen.
+    pub async fn new(ctx: &mut Context, level_index: usize) -> Self {
+        #[cfg(not(target_family = "wasm"))]
+        let seed = std::env::var(SEED_ENV_VAR)
+            .ok()
+            .and_then(|seed| seed.parse().ok())
+            .unwrap_or_else(Rng::seed_from_time);
+        #[cfg(target_family = "wasm")]
+        let seed = Rng::seed_from_time();
+
+        Self::with_seed(ctx, level_index, seed).await
     }
 
-    fn _draw_scene(&mut self) {
-        let source = self.player_position;
-        // Draw lines from light source to corners of squares
-        // Create rays, sweeping over 360 degrees
-        // TODO: Replace with only rays pointed at each intersection point in scene
-        let mut rays = vec![];
-        // let num_rays = 60;
-        let num_rays = 360;
-        for idx in 0..num_rays {
-            let ratio = idx as f32 / num_rays as f32;
-            // let angle = ratio * (2. * PI / 6.);
-            let angle = ratio * (2. * PI);
-            rays.push(Ray {
-                origin: source,
-                dir: Vec2::new(angle.cos(), angle.sin()),
-            })
-        }
+    /// starts the level with the given seed; runs with the same seed spawn the same things
+    pub async fn with_seed(ctx: &mut Context, level_index: usize, seed: u64) -> Self {
+        let pause_subscene = Pause::new(ctx);
+        let game_over_subscene = GameOver::new(ctx, level_index);
+        let level_complete_subscene = LevelComplete::new(ctx, level_index);
+        let win_subscene = Win::new(ctx);
 
-        let mut asteroids_segs = vec![];
-        for a in &self.asteroids {
-            for seg in a.to_segments() {
-                asteroids_segs.push(seg);
-            }
+        match &ctx.levels[level_index].music {
+            Some(track) => ctx.music.play(track),
+            None => ctx.music.stop(),
         }
 
-        // draw walls
-        for w in &self.walls {
-            draw_line(w.src.x, w.src.y, w.dst.x, w.dst.y, 4., BLUE);
+        Self {
+            pause_subscene,
+            game_over_subscene,
+            level_complete_subscene,
+            win_subscene,
+            gamepads_connected: ctx.gamepads.all().count(),
+            level_index,
+            seed,
+            world: World::new(ctx.levels[level_index].clone(), seed),
+            replay: Replay::new(&ctx.levels[level_index], seed),
+            playback: None,
+            touch_controls: TouchControls::default(),
         }
+    }
 
-        // for seg in &asteroids_segs {
-        //     draw_line(seg.src.x, seg.src.y, seg.dst.x, seg.dst.y, 2., BROWN);
-        // }
+    /// plays back the most recently saved replay, if there is one and its level still exists
+    pub async fn watch_last_replay(ctx: &mut Context) -> Option<Self> {
+        let replay = Replay::load_last()?;
+        let level_index = ctx
+            .levels
+            .iter()
+            .position(|level| level.id == replay.level_id)?;
+
+        let mut gameplay = Self::with_seed(ctx, level_index, replay.seed).await;
+        gameplay.playback = Some(replay.inputs().into_iter());
+        gameplay.replay = replay;
+        Some(gameplay)
+    }
 
+    fn _draw_scene(&mut self, ctx: &mut Context) {
+        // draw everything part way between where it was and where it is, so movement stays
+        // smooth when the frame rate doesn't line up with the fixed timestep
+        let alpha = ctx.interpolation;
+        let w = &self.world;
+        let source = w.prev_player_position.lerp(w.player_position, alpha);
+        let asteroid_rects: Vec<Rect> = w
+            .asteroids
+            .iter()
+            .map(|a| a.interpolated_rect(alpha))
+            .collect();
+
+        // everything that blocks light; the screen bounds keep the visibility polygon closed
         let mut collideable = vec![];
-        for w in &self.walls {
-            collideable.push(w);
+        collideable.extend_from_slice(&w.walls);
+        for r in &asteroid_rects {
+            collideable.extend_from_slice(&rect_segments(*r));
         }
-        for s in &asteroids_segs {
-            collideable.push(s);
+        collideable.extend_from_slice(&rect_segments(Rect::new(
+            0.,
+            0.,
+            VIRTUAL_WIDTH,
+            VIRTUAL_HEIGHT,
+        )));
+
+        let visible = visibility_polygon(source, &collideable);
+        draw_light(source, &visible, LIGHT_RADIUS, LIGHT_COLOR);
+
+        // draw asteroids on top of the light, so they sit at the edge of their own shadow
+        for r in &asteroid_rects {
+            draw_rectangle(r.x, r.y, r.w, r.h, ASTEROID_COLOR);
         }
 
-        // find intersections
-        let mut intersections = vec![];
-        for ray in &rays {
-            let mut closest_intersection: Option<Vec2> = None;
-            for w in &collideable {
-                if let Some(new_intersection) = ray.intersection(w) {
-                    if let Some(existing_int) = closest_intersection {
-                        if ray.origin.distance(existing_int)
-                            >= ray.origin.distance(new_intersection)
-                        {
-                            closest_intersection = Some(new_intersection);
-                        }
-                    } else {
-                        closest_intersection = Some(new_intersection);
-                    }
-                }
-            }
+        for c in &w.crystals {
+            let position = c.prev_position.lerp(c.circle.point(), alpha);
+            draw_poly(position.x, position.y, 4, c.circle.r, 0., CRYSTAL_COLOR);
+        }
 
-            // only keep the nearest one
-            if let Some(intersection) = closest_intersection {
-                intersections.push(intersection);
-            }
+        // draw walls
+        for w in &w.walls {
+            draw_line(w.src.x, w.src.y, w.dst.x, w.dst.y, 4., BLUE);
         }
 
-        // draw rays and interactions
-        for intersection in intersections {
-            draw_line(
+        if let Some(died_at) = w.died_at {
+            // the player is gone, replaced by an expanding, fading explosion
+            let progress = ((w.clock - died_at) / DEATH_FREEZE_DURATION).min(1.) as f32;
+            let color = Color {
+                a: 1. - progress,
+                ..EXPLOSION_COLOR
+            };
+            draw_circle(source.x, source.y, EXPLOSION_RADIUS * progress, color);
+            draw_circle_lines(
                 source.x,
                 source.y,
-                intersection.x,
-                intersection.y,
-                1.,
-                // WHITE,
-                Color::new(1.00, 1.00, 1.00, 0.5),
+                EXPLOSION_RADIUS * progress.sqrt(),
+                3.,
+                color,
             );
-            // intersection point
-            draw_circle(intersection.x, intersection.y, 2., ORANGE);
+        } else {
+            if let Some(warped_at) = w.warped_at {
+                // light streaks trailing behind the player, growing as they speed up
+                let speed = WARP_ACCELERATION * (w.clock - warped_at) as f32;
+                let streak_len = speed * WARP_STREAK_FACTOR;
+                for offset in [-PLAYER_RADIUS, 0., PLAYER_RADIUS] {
+                    let y = source.y + offset;
+                    draw_line(source.x - streak_len, y, source.x, y, 2., CRYSTAL_COLOR);
+                }
+            }
+            // Draw Player (light source)
+            draw_circle(source.x, source.y, PLAYER_RADIUS, WHITE);
         }
-
-        // Draw Player (light source)
-        draw_circle(source.x, source.y, PLAYER_RADIUS, WHITE);
-        // draw_texture(&ctx.textures.example, 400., 300., WHITE);
+        // draw_texture(ctx.textures.get("example"), 400., 300., WHITE);
 
         // draw bullet
-        for b in &self.bullets {
-            draw_circle(b.circle.x, b.circle.y, b.circle.r, BULLET_COLOR);
+        for b in &w.bullets {
+            let position = b.prev_position.lerp(b.circle.point(), alpha);
+            draw_circle(position.x, position.y, b.circle.r, BULLET_COLOR);
         }
-    }
 
-    fn player_movement(&mut self, ctx: &mut Context) {
-        let mut movement_vec = Vec2::new(0., 0.);
-        if action_down(Action::Up, &ctx.gamepads) {
-            movement_vec.y += -1.;
-        }
-        if action_down(Action::Down, &ctx.gamepads) {
-            movement_vec.y += 1.;
-        }
-        if action_down(Action::Left, &ctx.gamepads) {
-            movement_vec.x += -1.;
-        }
-        if action_down(Action::Right, &ctx.gamepads) {
-            movement_vec.x += 1.;
-        }
-
-        let delta = get_frame_time();
-        if movement_vec != Vec2::new(0., 0.) {
-            self.player_position += MOVEMENT_SPEED * delta * movement_vec.normalize();
-            self.player_position = self
-                .player_position
-                .clamp(Vec2::default(), Vec2::new(VIRTUAL_WIDTH, VIRTUAL_HEIGHT));
-        }
-    }
-
-    fn player_attack(&mut self, ctx: &mut Context) {
-        let delta = get_frame_time();
-        for b in &mut self.bullets {
-            b.circle.x += BULLET_MOVEMENT_SPEED * delta;
-        }
-        // TODO: Add another action for "attack"
-        if action_pressed(Action::Confirm, &ctx.gamepads) {
-            self.bullets.push(Bullet {
-                circle: Circle::new(
-                    self.player_position.x + 10.,
-                    self.player_position.y,
-                    BULLET_RADIUS,
-                ),
-            });
-        }
-    }
+        draw_text(
+            ctx,
+            format!(
+                "Warp crystals: {}/{}",
+                w.crystals_collected, w.level.crystal_quota
+            )
+            .as_str(),
+            20.,
+            40.,
+            Size::Small,
+            WHITE,
+        );
 
-    fn asteroid_movement(&mut self) {
-        let asteroid_movement_speed = 50.;
-        let delta = get_frame_time();
-        for a in &mut self.asteroids {
-            a.rect.x -= asteroid_movement_speed * delta;
+        if self.playback.is_some() {
+            draw_text(ctx, "Replay", 20., 70., Size::Small, RED);
+        } else {
+            self.touch_controls.draw();
         }
     }
+}
 
-    fn spawn_asteroid(&mut self) {
-        // random y
-        let ratio_y = rand() as f32 / u32::MAX as f32;
-        // random height
-        let ratio_h = rand() as f32 / u32::MAX as f32;
-
-        self.asteroids.push(Asteroid {
-            rect: Rect::new(
-                VIRTUAL_WIDTH,
-                (ratio_y * VIRTUAL_HEIGHT).floor(),
-                30.,
-                20. + (180. * ratio_h).floor(),
-            ),
-        })
-    }
+/// plays the sound for something that happened in the world, panned to where it happened
+fn play_event_sfx(ctx: &mut Context, event: &WorldEvent) {
+    let (id, position) = match event {
+        WorldEvent::Fired(position) => (audio::SHOOT, position),
+        WorldEvent::AsteroidHit(position) => (audio::ASTEROID_HIT, position),
+        WorldEvent::CrystalCollected(position) => (audio::CRYSTAL, position),
+        WorldEvent::Died(position) => (audio::DEATH, position),
+        WorldEvent::Warped(position) => (audio::WARP, position),
+    };
+    ctx.audio.varied_sfx_mut(id).play(&ctx.settings, position.x);
 }
@@ -0,0 +1,111 @@
+use macroquad::color::WHITE;
+
+use super::EScene;
+use super::Scene;
+use crate::consts::TITLE_Y_INSET;
+use crate::consts::X_INSET;
+use crate::menu::{Menu, MenuEvent};
+use crate::text::Size;
+use crate::{context::Context, text::draw_text};
+
+/// sub-scene rendered once the player has warped out, showing how the level went
+pub struct LevelComplete {
+    pub active: bool,
+    menu: Menu<MenuOption>,
+    /// index of the level that was completed
+    level_index: usize,
+    /// how long the level took, in seconds
+    time: f64,
+    /// how many warp crystals the player collected
+    crystals: u32,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum MenuOption {
+    NextLevel,
+    PlayAgain,
+    MainMenu,
+}
+
+impl LevelComplete {
+    pub fn new(ctx: &mut Context, level_index: usize) -> Self {
+        let mut menu_options = vec![MenuOption::PlayAgain, MenuOption::MainMenu];
+        if level_index + 1 < ctx.levels.len() {
+            menu_options.insert(0, MenuOption::NextLevel);
+        }
+
+        Self {
+            menu: Menu::new(menu_options, X_INSET, 320.),
+            level_index,
+            active: false,
+            time: 0.,
+            crystals: 0,
+        }
+    }
+
+    /// activates the sub-scene with the results of the level
+    pub fn show(&mut self, time: f64, crystals: u32) {
+        self.active = true;
+        self.menu.reset();
+        self.time = time;
+        self.crystals = crystals;
+    }
+
+    fn text_for_menu_option(menu_option: MenuOption) -> &'static str {
+        match menu_option {
+            MenuOption::NextLevel => "Next Level",
+            MenuOption::PlayAgain => "Play Again",
+            MenuOption::MainMenu => "Return to Main Menu",
+        }
+    }
+}
+
+impl Scene for LevelComplete {
+    fn update(&mut self, ctx: &mut Context) {
+        if let Some(MenuEvent::Confirm(menu_option)) = self.menu.update(ctx) {
+            match menu_option {
+                MenuOption::NextLevel => {
+                    ctx.switch_scene_to = Some(EScene::Gameplay(self.level_index + 1));
+                }
+                MenuOption::PlayAgain => {
+                    ctx.switch_scene_to = Some(EScene::Gameplay(self.level_index));
+                }
+                MenuOption::MainMenu => {
+                    ctx.switch_scene_to = Some(EScene::MainMenu);
+                }
+            }
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut Context) {
+        draw_text(
+            ctx,
+            "Level Complete",
+            X_INSET,
+            TITLE_Y_INSET,
+            Size::Large,
+            WHITE,
+        );
+
+        draw_text(
+            ctx,
+            format!("Time: {:.1}s", self.time).as_str(),
+            X_INSET,
+            200.,
+            Size::Medium,
+            WHITE,
+        );
+        draw_text(
+            ctx,
+            format!("Warp crystals: {}", self.crystals).as_str(),
+            X_INSET,
+            240.,
+            Size::Medium,
+            WHITE,
+        );
+
+        self.menu.draw(ctx, |_ctx, menu_option| {
+            Self::text_for_menu_option(menu_option).to_string()
+        });
+    }
+}
@@ -0,0 +1,104 @@
+use macroquad::color::WHITE;
+
+use super::EScene;
+use super::Scene;
+use crate::audio::{self, play_sfx};
+use crate::consts::TITLE_Y_INSET;
+use crate::consts::X_INSET;
+use crate::input::Action;
+use crate::menu::{Menu, MenuEvent};
+use crate::text::Size;
+use crate::{context::Context, text::draw_text};
+
+/// sub-scene for picking which unlocked level to play
+pub struct LevelSelect {
+    pub active: bool,
+    menu: Menu<MenuOption>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum MenuOption {
+    /// index of the level in `Context::levels`
+    Level(usize),
+    Back,
+}
+
+impl LevelSelect {
+    pub fn new(ctx: &Context) -> Self {
+        let mut menu_options: Vec<MenuOption> =
+            (0..ctx.levels.len()).map(MenuOption::Level).collect();
+        menu_options.push(MenuOption::Back);
+
+        // locked levels are shown but can't be picked
+        let mut menu = Menu::new(menu_options, X_INSET, 200.);
+        for index in 0..ctx.levels.len() {
+            menu.set_enabled(
+                MenuOption::Level(index),
+                ctx.save.is_level_unlocked(&ctx.levels, index),
+            );
+        }
+
+        Self {
+            menu,
+            active: false,
+        }
+    }
+
+    fn text_for_menu_option(ctx: &Context, menu_option: MenuOption) -> String {
+        match menu_option {
+            MenuOption::Level(index) => {
+                let level = &ctx.levels[index];
+                let title = format!("{}. {}", index + 1, level.name);
+                if !ctx.save.is_level_unlocked(&ctx.levels, index) {
+                    return format!("{title} (locked)");
+                }
+
+                match ctx.save.level_progress(level) {
+                    Some(progress) => match progress.best_time {
+                        Some(best_time) => format!(
+                            "{title} - best {:.1}s, {} crystals",
+                            best_time, progress.crystals
+                        ),
+                        None => format!("{title} - {} crystals", progress.crystals),
+                    },
+                    None => title,
+                }
+            }
+            MenuOption::Back => "Back".to_string(),
+        }
+    }
+}
+
+impl Scene for LevelSelect {
+    fn update(&mut self, ctx: &mut Context) {
+        if ctx.input.pressed(Action::Cancel) {
+            self.active = false;
+            play_sfx(ctx, audio::MENU_CANCEL);
+            return;
+        }
+
+        if let Some(MenuEvent::Confirm(menu_option)) = self.menu.update(ctx) {
+            match menu_option {
+                MenuOption::Level(index) => {
+                    ctx.switch_scene_to = Some(EScene::Gameplay(index));
+                }
+                MenuOption::Back => {
+                    self.active = false;
+                }
+            }
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut Context) {
+        draw_text(
+            ctx,
+            "Select Level",
+            X_INSET,
+            TITLE_Y_INSET,
+            Size::Large,
+            WHITE,
+        );
+
+        self.menu.draw(ctx, Self::text_for_menu_option);
+    }
+}
@@ -0,0 +1,95 @@
+use macroquad::color::{GRAY, WHITE};
+use macroquad::shapes::{draw_rectangle, draw_rectangle_lines};
+
+use super::EScene;
+use super::Scene;
+use crate::consts::TITLE_Y_INSET;
+use crate::consts::VIRTUAL_HEIGHT;
+use crate::consts::VIRTUAL_WIDTH;
+use crate::consts::X_INSET;
+use crate::loader::Loader;
+use crate::rng::Rng;
+use crate::text::Size;
+use crate::{context::Context, text::draw_text};
+
+const PROGRESS_BAR_Y: f32 = 340.;
+const PROGRESS_BAR_HEIGHT: f32 = 24.;
+
+/// one of these is shown while loading
+const TIPS: [&str; 5] = [
+    "Tip: controls can be rebound in Settings",
+    "Tip: the music can be turned down without muting the sound effects",
+    "Tip: watch a replay of your last run from the game over screen",
+    "Tip: every run's seed is shown on the game over screen",
+    "Tip: collect enough warp crystals to finish a level",
+];
+
+/// shows a progress bar while the loader brings in assets, then moves on
+pub struct Loading {
+    loader: Loader,
+    /// the scene to switch to once everything has loaded
+    next: EScene,
+    tip: &'static str,
+}
+
+impl Loading {
+    pub fn new(loader: Loader, next: EScene) -> Self {
+        let mut rng = Rng::new(Rng::seed_from_time());
+        let tip = TIPS[(rng.ratio() * TIPS.len() as f32) as usize % TIPS.len()];
+        Self { loader, next, tip }
+    }
+
+    /// loads the level's own assets before playing it, `None` if it has none or they've already
+    /// been loaded
+    pub fn for_level(ctx: &mut Context, level: usize) -> Option<Self> {
+        let level_data = ctx.levels.get(level)?;
+        if level_data.assets.is_empty() || !ctx.loaded_level_assets.insert(level_data.id.clone()) {
+            return None;
+        }
+        let loader = Loader::manifest(ctx.assets_path.clone(), &level_data.assets);
+        Some(Self::new(loader, EScene::Gameplay(level)))
+    }
+}
+
+impl Scene for Loading {
+    fn update(&mut self, ctx: &mut Context) {
+        self.loader.update(ctx);
+        if self.loader.is_done() && ctx.switch_scene_to.is_none() {
+            ctx.switch_scene_to = Some(if ctx.asset_errors.is_empty() {
+                self.next.clone()
+            } else {
+                EScene::AssetErrors(Box::new(self.next.clone()))
+            });
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut Context) {
+        draw_text(ctx, "Loading", X_INSET, TITLE_Y_INSET, Size::Large, WHITE);
+
+        let width = VIRTUAL_WIDTH - X_INSET * 2.;
+        draw_rectangle(
+            X_INSET,
+            PROGRESS_BAR_Y,
+            width * self.loader.progress(),
+            PROGRESS_BAR_HEIGHT,
+            WHITE,
+        );
+        draw_rectangle_lines(
+            X_INSET,
+            PROGRESS_BAR_Y,
+            width,
+            PROGRESS_BAR_HEIGHT,
+            2.,
+            WHITE,
+        );
+
+        draw_text(
+            ctx,
+            self.tip,
+            X_INSET,
+            VIRTUAL_HEIGHT - 120.,
+            Size::Small,
+            GRAY,
+        );
+    }
+}
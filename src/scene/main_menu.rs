@@ -1,20 +1,23 @@
 use super::credits::Credits;
+use super::level_select::LevelSelect;
 use super::settings::Settings;
-use super::{EScene, Scene};
-use crate::audio::play_sfx;
+use super::Scene;
 use crate::consts::*;
 use crate::context::Context;
-use crate::input::{action_pressed, Action};
+use crate::input::Action;
+use crate::menu::{Menu, MenuEvent};
+use crate::music::MENU_TRACK;
 use crate::text::{self, draw_text};
-use macroquad::color::{RED, WHITE};
+use macroquad::color::WHITE;
 
 pub struct MainMenu {
-    menu_options: Vec<MenuOption>,
-    menu_index: usize,
+    menu: Menu<MenuOption>,
     settings_subscene: Settings,
     credits_subscene: Credits,
+    level_select_subscene: LevelSelect,
 }
 
+#[derive(Clone, Copy, PartialEq)]
 enum MenuOption {
     Play,
     Settings,
@@ -25,6 +28,8 @@ enum MenuOption {
 
 impl MainMenu {
     pub async fn new(ctx: &mut Context) -> Self {
+        ctx.music.play(MENU_TRACK);
+
         let menu_options = vec![
             MenuOption::Play,
             MenuOption::Settings,
@@ -34,14 +39,14 @@ impl MainMenu {
         ];
 
         Self {
-            menu_options,
-            menu_index: 0,
+            menu: Menu::new(menu_options, X_INSET, 400.),
             settings_subscene: Settings::new(ctx, false),
             credits_subscene: Credits::new(ctx),
+            level_select_subscene: LevelSelect::new(ctx),
         }
     }
 
-    fn text_for_menu_option(&self, menu_option: &MenuOption) -> &str {
+    fn text_for_menu_option(menu_option: MenuOption) -> &'static str {
         match menu_option {
             MenuOption::Play => "Play",
             MenuOption::Settings => "Settings",
@@ -64,17 +69,15 @@ impl Scene for MainMenu {
             return;
         }
 
-        let menu_option = self
-            .menu_options
-            .get(self.menu_index)
-            .expect("pause menu index out of bounds");
-
-        if action_pressed(Action::Confirm, &ctx.gamepads) {
-            play_sfx(ctx, &ctx.audio.sfx.menu_select);
+        if self.level_select_subscene.active {
+            self.level_select_subscene.update(ctx);
+            return;
+        }
 
+        if let Some(MenuEvent::Confirm(menu_option)) = self.menu.update(ctx) {
             match menu_option {
                 MenuOption::Play => {
-                    ctx.switch_scene_to = Some(EScene::Gameplay);
+                    self.level_select_subscene.active = true;
                 }
                 MenuOption::Settings => {
                     self.settings_subscene.active = true;
@@ -88,25 +91,6 @@ impl Scene for MainMenu {
                 }
             }
         }
-
-        if action_pressed(Action::Up, &ctx.gamepads) {
-            play_sfx(ctx, &ctx.audio.sfx.menu_move);
-
-            if self.menu_index == 0 {
-                self.menu_index = self.menu_options.len() - 1;
-            } else {
-                self.menu_index -= 1;
-            }
-        }
-        if action_pressed(Action::Down, &ctx.gamepads) {
-            play_sfx(ctx, &ctx.audio.sfx.menu_move);
-
-            if self.menu_index == self.menu_options.len() - 1 {
-                self.menu_index = 0;
-            } else {
-                self.menu_index += 1;
-            }
-        }
     }
     fn draw(&mut self, ctx: &mut Context) {
         if self.settings_subscene.active {
@@ -119,6 +103,11 @@ impl Scene for MainMenu {
             return;
         }
 
+        if self.level_select_subscene.active {
+            self.level_select_subscene.draw(ctx);
+            return;
+        }
+
         draw_text(
             ctx,
             crate::consts::PKG_NAME,
@@ -128,22 +117,20 @@ impl Scene for MainMenu {
             WHITE,
         );
 
-        for (i, menu_option) in self.menu_options.iter().enumerate() {
-            let color = if self.menu_index == i { RED } else { WHITE };
-
-            draw_text(
-                ctx,
-                self.text_for_menu_option(menu_option),
-                X_INSET,
-                400. + (i as f32 * 40.),
-                text::Size::Medium,
-                color,
-            );
-        }
+        self.menu.draw(ctx, |_ctx, menu_option| {
+            Self::text_for_menu_option(menu_option).to_string()
+        });
 
+        let bindings = ctx.settings.bindings();
+        let hint = format!(
+            "Up = {} | Down = {} | Confirm = {}",
+            bindings.describe(Action::Up),
+            bindings.describe(Action::Down),
+            bindings.describe(Action::Confirm)
+        );
         draw_text(
             ctx,
-            "Change Select = Arrow Keys | Confirm = Z",
+            &hint,
             X_INSET,
             VIRTUAL_HEIGHT - 40.,
             text::Size::Small,
@@ -1,24 +1,24 @@
-use macroquad::color::{RED, WHITE};
+use macroquad::color::WHITE;
 
 use super::settings::Settings;
 use super::EScene;
 use super::Scene;
-use crate::audio::play_sfx;
+use crate::audio::{self, play_sfx};
 use crate::consts::TITLE_Y_INSET;
 use crate::consts::X_INSET;
-use crate::input::action_pressed;
 use crate::input::Action;
+use crate::menu::{Menu, MenuEvent};
 use crate::text::Size;
 use crate::{context::Context, text::draw_text};
 
 /// sub-scene rendered during gameplay with various options
 pub struct Pause {
     pub active: bool,
-    menu_options: Vec<MenuOption>,
-    menu_index: usize,
+    menu: Menu<MenuOption>,
     settings_subscene: Settings,
 }
 
+#[derive(Clone, Copy, PartialEq)]
 enum MenuOption {
     Resume,
     Settings,
@@ -38,14 +38,13 @@ impl Pause {
         ];
 
         Self {
-            menu_options,
-            menu_index: 0,
+            menu: Menu::new(menu_options, X_INSET, 200.),
             active: false,
             settings_subscene: Settings::new(ctx, false),
         }
     }
 
-    fn text_for_menu_option(&self, menu_option: &MenuOption) -> &str {
+    fn text_for_menu_option(menu_option: MenuOption) -> &'static str {
         match menu_option {
             MenuOption::Resume => "Resume",
             MenuOption::Settings => "Settings",
@@ -63,40 +62,13 @@ impl Scene for Pause {
             return;
         }
 
-        if action_pressed(Action::Pause, &ctx.gamepads)
-            || action_pressed(Action::Cancel, &ctx.gamepads)
-        {
+        if ctx.input.pressed(Action::Pause) || ctx.input.pressed(Action::Cancel) {
             self.active = false;
-            play_sfx(ctx, &ctx.audio.sfx.menu_cancel);
+            play_sfx(ctx, audio::MENU_CANCEL);
             return;
         }
 
-        if action_pressed(Action::Up, &ctx.gamepads) {
-            play_sfx(ctx, &ctx.audio.sfx.menu_move);
-
-            if self.menu_index == 0 {
-                self.menu_index = self.menu_options.len() - 1;
-            } else {
-                self.menu_index -= 1;
-            }
-        }
-        if action_pressed(Action::Down, &ctx.gamepads) {
-            play_sfx(ctx, &ctx.audio.sfx.menu_move);
-
-            if self.menu_index == self.menu_options.len() - 1 {
-                self.menu_index = 0;
-            } else {
-                self.menu_index += 1;
-            }
-        }
-
-        if action_pressed(Action::Confirm, &ctx.gamepads) {
-            play_sfx(ctx, &ctx.audio.sfx.menu_select);
-
-            let menu_option = self
-                .menu_options
-                .get(self.menu_index)
-                .expect("pause menu index out of bounds");
+        if let Some(MenuEvent::Confirm(menu_option)) = self.menu.update(ctx) {
             match menu_option {
                 MenuOption::Resume => {
                     self.active = false;
@@ -123,17 +95,8 @@ impl Scene for Pause {
 
         draw_text(ctx, "Pause", X_INSET, TITLE_Y_INSET, Size::Large, WHITE);
 
-        for (i, menu_option) in self.menu_options.iter().enumerate() {
-            let color = if self.menu_index == i { RED } else { WHITE };
-
-            draw_text(
-                ctx,
-                self.text_for_menu_option(menu_option),
-                X_INSET,
-                200. + (i as f32 * 40.),
-                Size::Medium,
-                color,
-            );
-        }
+        self.menu.draw(ctx, |_ctx, menu_option| {
+            Self::text_for_menu_option(menu_option).to_string()
+        });
     }
 }
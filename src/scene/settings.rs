@@ -1,41 +1,70 @@
-use macroquad::color::{RED, WHITE};
+use macroquad::color::WHITE;
 
+use super::controls::Controls;
 use super::Scene;
-use crate::audio::play_sfx;
+use crate::audio::{self, play_sfx};
 use crate::consts::X_INSET;
-use crate::input::action_pressed;
 use crate::input::Action;
+use crate::menu::{Menu, MenuEvent};
+use crate::settings::Volume;
 use crate::text::Size;
 use crate::{context::Context, text::draw_text};
 
 /// sub-scene for setting user preferences, at times a subscene
 pub struct Settings {
     pub active: bool,
-    menu_options: Vec<MenuOption>,
-    menu_index: usize,
+    menu: Menu<MenuOption>,
+    controls_subscene: Controls,
 }
 
+#[derive(Clone, Copy, PartialEq)]
 enum MenuOption {
     Fullscreen,
     Mute,
+    Volume(Volume),
+    StickDeadzone,
+    StickSnap,
+    Controls,
     Back,
 }
 
 impl Settings {
-    pub fn new(_ctx: &Context, active: bool) -> Self {
-        let menu_options = vec![MenuOption::Fullscreen, MenuOption::Mute, MenuOption::Back];
+    pub fn new(ctx: &Context, active: bool) -> Self {
+        let menu_options = vec![
+            MenuOption::Fullscreen,
+            MenuOption::Mute,
+            MenuOption::Volume(Volume::Master),
+            MenuOption::Volume(Volume::Music),
+            MenuOption::Volume(Volume::Sfx),
+            MenuOption::StickDeadzone,
+            MenuOption::StickSnap,
+            MenuOption::Controls,
+            MenuOption::Back,
+        ];
+
+        let mut menu = Menu::new(menu_options, X_INSET, 200.);
+        for menu_option in [
+            MenuOption::Fullscreen,
+            MenuOption::Mute,
+            MenuOption::Volume(Volume::Master),
+            MenuOption::Volume(Volume::Music),
+            MenuOption::Volume(Volume::Sfx),
+            MenuOption::StickDeadzone,
+            MenuOption::StickSnap,
+        ] {
+            menu.set_adjustable(menu_option, true);
+        }
 
         Self {
-            menu_options,
-            menu_index: 0,
+            menu,
             active,
+            controls_subscene: Controls::new(ctx),
         }
     }
 
     fn text_for_menu_option(
-        &self,
         settings: &crate::settings::Settings,
-        menu_option: &MenuOption,
+        menu_option: MenuOption,
     ) -> String {
         match menu_option {
             MenuOption::Back => "Back".to_string(),
@@ -43,45 +72,38 @@ impl Settings {
                 format!("Fullscreen: {}", settings.is_fullscreen())
             }
             MenuOption::Mute => format!("Mute: {}", settings.is_muted()),
+            MenuOption::Volume(volume) => {
+                let name = match volume {
+                    Volume::Master => "Master Volume",
+                    Volume::Music => "Music Volume",
+                    Volume::Sfx => "SFX Volume",
+                };
+                format!("{name}: {}", settings.volume(volume))
+            }
+            MenuOption::StickDeadzone => {
+                format!("Stick Deadzone: {:.0}%", settings.stick_deadzone() * 100.)
+            }
+            MenuOption::StickSnap => format!("Stick Snap: {}", settings.is_stick_snap()),
+            MenuOption::Controls => "Controls".to_string(),
         }
     }
 }
 
 impl Scene for Settings {
     fn update(&mut self, ctx: &mut Context) {
-        if action_pressed(Action::Cancel, &ctx.gamepads) {
-            self.active = false;
-            play_sfx(ctx, &ctx.audio.sfx.menu_cancel);
+        if self.controls_subscene.active {
+            self.controls_subscene.update(ctx);
             return;
         }
 
-        if action_pressed(Action::Up, &ctx.gamepads) {
-            play_sfx(ctx, &ctx.audio.sfx.menu_move);
-
-            if self.menu_index == 0 {
-                self.menu_index = self.menu_options.len() - 1;
-            } else {
-                self.menu_index -= 1;
-            }
-        }
-        if action_pressed(Action::Down, &ctx.gamepads) {
-            play_sfx(ctx, &ctx.audio.sfx.menu_move);
-
-            if self.menu_index == self.menu_options.len() - 1 {
-                self.menu_index = 0;
-            } else {
-                self.menu_index += 1;
-            }
+        if ctx.input.pressed(Action::Cancel) {
+            self.active = false;
+            play_sfx(ctx, audio::MENU_CANCEL);
+            return;
         }
 
-        if action_pressed(Action::Confirm, &ctx.gamepads) {
-            play_sfx(ctx, &ctx.audio.sfx.menu_select);
-
-            let menu_option = self
-                .menu_options
-                .get(self.menu_index)
-                .expect("pause menu index out of bounds");
-            match menu_option {
+        match self.menu.update(ctx) {
+            Some(MenuEvent::Confirm(menu_option)) => match menu_option {
                 MenuOption::Back => {
                     self.active = false;
                 }
@@ -91,25 +113,51 @@ impl Scene for Settings {
                 MenuOption::Mute => {
                     ctx.settings.toggle_mute();
                 }
-            }
+                MenuOption::Volume(volume) => {
+                    ctx.settings.cycle_volume(volume);
+                }
+                MenuOption::StickDeadzone => {
+                    ctx.settings.cycle_stick_deadzone();
+                }
+                MenuOption::StickSnap => {
+                    ctx.settings.toggle_stick_snap();
+                }
+                MenuOption::Controls => {
+                    self.controls_subscene.active = true;
+                }
+            },
+            Some(MenuEvent::Adjust(menu_option, step)) => match menu_option {
+                MenuOption::Fullscreen => {
+                    ctx.settings.toggle_fullscreen();
+                }
+                MenuOption::Mute => {
+                    ctx.settings.toggle_mute();
+                }
+                MenuOption::Volume(volume) => {
+                    ctx.settings.step_volume(volume, step);
+                }
+                MenuOption::StickDeadzone => {
+                    ctx.settings.step_stick_deadzone(step);
+                }
+                MenuOption::StickSnap => {
+                    ctx.settings.toggle_stick_snap();
+                }
+                MenuOption::Controls | MenuOption::Back => {}
+            },
+            None => {}
         }
     }
 
     fn draw(&mut self, ctx: &mut Context) {
-        draw_text(ctx, "Settings", X_INSET, 128., Size::Large, WHITE);
+        if self.controls_subscene.active {
+            self.controls_subscene.draw(ctx);
+            return;
+        }
 
-        for (i, menu_option) in self.menu_options.iter().enumerate() {
-            let color = if self.menu_index == i { RED } else { WHITE };
+        draw_text(ctx, "Settings", X_INSET, 128., Size::Large, WHITE);
 
-            let text = self.text_for_menu_option(&ctx.settings, menu_option);
-            draw_text(
-                ctx,
-                text.as_str(),
-                X_INSET,
-                200. + (i as f32 * 40.),
-                Size::Medium,
-                color,
-            );
-        }
+        self.menu.draw(ctx, |ctx, menu_option| {
+            Self::text_for_menu_option(&ctx.settings, menu_option)
+        });
     }
 }
@@ -0,0 +1,72 @@
+use macroquad::color::{RED, WHITE};
+
+use super::EScene;
+use super::{return_hint, Scene};
+use crate::audio::{self, play_sfx};
+use crate::consts::TITLE_Y_INSET;
+use crate::consts::VIRTUAL_HEIGHT;
+use crate::consts::X_INSET;
+use crate::input::Action;
+use crate::text::Size;
+use crate::{context::Context, text::draw_text};
+
+/// sub-scene rendered once the player has completed the final level
+pub struct Win {
+    pub active: bool,
+}
+
+impl Win {
+    pub fn new(_ctx: &Context) -> Self {
+        Self { active: false }
+    }
+}
+
+impl Scene for Win {
+    fn update(&mut self, ctx: &mut Context) {
+        if ctx.input.pressed(Action::Cancel)
+            || ctx.input.pressed(Action::Confirm)
+            || ctx.input.pointer_pressed()
+        {
+            play_sfx(ctx, audio::MENU_SELECT);
+            ctx.switch_scene_to = Some(EScene::MainMenu);
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut Context) {
+        draw_text(ctx, "You Win!", X_INSET, TITLE_Y_INSET, Size::Large, WHITE);
+
+        draw_text(
+            ctx,
+            format!("All {} levels complete.", ctx.levels.len()).as_str(),
+            X_INSET,
+            240.,
+            Size::Medium,
+            WHITE,
+        );
+
+        // the sum of the best times is the run to beat
+        let total_best_time: f64 = ctx
+            .levels
+            .iter()
+            .filter_map(|level| ctx.save.level_progress(level))
+            .filter_map(|progress| progress.best_time)
+            .sum();
+        draw_text(
+            ctx,
+            format!("Total best time: {:.1}s", total_best_time).as_str(),
+            X_INSET,
+            280.,
+            Size::Medium,
+            WHITE,
+        );
+
+        draw_text(
+            ctx,
+            &return_hint(ctx),
+            X_INSET,
+            VIRTUAL_HEIGHT - 120.,
+            Size::Medium,
+            RED,
+        );
+    }
+}
@@ -1,5 +1,11 @@
 #[cfg(not(target_family = "wasm"))]
 use crate::dir;
+use crate::input::bindings::Bindings;
+use crate::input::Action;
+use gamepads::Button;
+use macroquad::input::KeyCode;
+#[cfg(not(target_family = "wasm"))]
+use macroquad::logging::warn;
 use macroquad::window::set_fullscreen;
 use serde::{Deserialize, Serialize};
 #[cfg(not(target_family = "wasm"))]
@@ -10,14 +16,67 @@ use std::path::PathBuf;
 pub struct Settings {
     /// whether or not audio should play
     mute: bool,
+    /// loudness of everything, from 0 to 100
+    #[serde(default = "default_volume")]
+    master_volume: u8,
+    /// loudness of the music, from 0 to 100, scaled by the master volume
+    #[serde(default = "default_volume")]
+    music_volume: u8,
+    /// loudness of the sound effects, from 0 to 100, scaled by the master volume
+    #[serde(default = "default_volume")]
+    sfx_volume: u8,
     /// whether or not the window should take up the entire screen
     fullscreen: bool,
+    /// how far the stick has to be pushed before it does anything, from 0 to 1
+    #[serde(default = "default_stick_deadzone")]
+    stick_deadzone: f32,
+    /// whether stick movement is rounded to the nearest of 8 directions
+    #[serde(default)]
+    stick_snap: bool,
+    /// which keys and buttons trigger each action
+    #[serde(default)]
+    bindings: Bindings,
+}
+
+/// the deadzones the player can pick from, cycling through them
+const STICK_DEADZONES: [f32; 8] = [0.05, 0.1, 0.15, 0.2, 0.25, 0.3, 0.35, 0.4];
+
+fn default_stick_deadzone() -> f32 {
+    0.2
+}
+
+/// which volume level to read or change
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Volume {
+    Master,
+    Music,
+    Sfx,
+}
+
+const MAX_VOLUME: u8 = 100;
+/// how much the volume changes with each press on its slider
+const VOLUME_STEP: u8 = 10;
+
+fn default_volume() -> u8 {
+    MAX_VOLUME
 }
 
 #[cfg(target_family = "wasm")]
 const FULLSCREEN: &str = "fullscreen";
 #[cfg(target_family = "wasm")]
 const MUTE: &str = "mute";
+#[cfg(target_family = "wasm")]
+const MASTER_VOLUME: &str = "master_volume";
+#[cfg(target_family = "wasm")]
+const MUSIC_VOLUME: &str = "music_volume";
+#[cfg(target_family = "wasm")]
+const SFX_VOLUME: &str = "sfx_volume";
+#[cfg(target_family = "wasm")]
+const STICK_DEADZONE: &str = "stick_deadzone";
+#[cfg(target_family = "wasm")]
+const STICK_SNAP: &str = "stick_snap";
+#[cfg(target_family = "wasm")]
+const BINDINGS: &str = "bindings";
 #[cfg(not(target_family = "wasm"))]
 const SETTINGS_FILE: &str = "settings.toml";
 
@@ -26,6 +85,12 @@ impl Settings {
         Settings {
             fullscreen: false,
             mute: false,
+            master_volume: default_volume(),
+            music_volume: default_volume(),
+            sfx_volume: default_volume(),
+            stick_deadzone: default_stick_deadzone(),
+            stick_snap: false,
+            bindings: Bindings::default(),
         }
     }
 
@@ -53,7 +118,30 @@ impl Settings {
         if let Some(storage_mute) = storage.get(MUTE) {
             settings.mute = storage_mute == "true";
         }
-        settings
+        for (key, volume) in [
+            (MASTER_VOLUME, &mut settings.master_volume),
+            (MUSIC_VOLUME, &mut settings.music_volume),
+            (SFX_VOLUME, &mut settings.sfx_volume),
+        ] {
+            if let Some(storage_volume) = storage.get(key) {
+                *volume = storage_volume
+                    .parse::<u8>()
+                    .unwrap_or(MAX_VOLUME)
+                    .min(MAX_VOLUME);
+            }
+        }
+        if let Some(storage_stick_deadzone) = storage.get(STICK_DEADZONE) {
+            settings.stick_deadzone = storage_stick_deadzone
+                .parse()
+                .unwrap_or(default_stick_deadzone());
+        }
+        if let Some(storage_stick_snap) = storage.get(STICK_SNAP) {
+            settings.stick_snap = storage_stick_snap == "true";
+        }
+        if let Some(storage_bindings) = storage.get(BINDINGS) {
+            settings.bindings = toml::from_str(storage_bindings.as_str()).unwrap_or_default();
+        }
+        settings.clamped()
     }
 
     #[cfg(not(target_family = "wasm"))]
@@ -63,13 +151,60 @@ impl Settings {
         if settings_path.exists() {
             let toml_str =
                 std::fs::read_to_string(settings_path).expect("couldn't read settings file");
-            let settings: Settings = toml::from_str(toml_str.as_str()).unwrap();
-            settings
+            Self::from_toml(&toml_str)
         } else {
             Self::default()
         }
     }
 
+    /// reads the settings file, which players can edit by hand, so the defaults are used for the
+    /// bindings or the rest if they can't be read rather than refusing to start
+    #[cfg(not(target_family = "wasm"))]
+    fn from_toml(toml_str: &str) -> Self {
+        let mut table: toml::Table = match toml_str.parse() {
+            Ok(table) => table,
+            Err(e) => {
+                warn!("couldn't read the settings file, using the defaults: {e}");
+                return Self::default();
+            }
+        };
+        let bindings = table.remove("bindings");
+
+        let mut settings: Settings = table.try_into().unwrap_or_else(|e| {
+            warn!("couldn't read the settings file, using the defaults: {e}");
+            Self::default()
+        });
+        if let Some(bindings) = bindings {
+            match bindings.try_into() {
+                Ok(bindings) => settings.bindings = bindings,
+                Err(e) => warn!("couldn't read the key bindings, using the defaults: {e}"),
+            }
+        }
+        settings.clamped()
+    }
+
+    /// pulls hand-edited values back into range, so a volume can't go over 100 or a deadzone past
+    /// where the stick's direction would flip
+    fn clamped(mut self) -> Self {
+        for volume in [
+            &mut self.master_volume,
+            &mut self.music_volume,
+            &mut self.sfx_volume,
+        ] {
+            *volume = (*volume).min(MAX_VOLUME);
+        }
+        let (min, max) = (
+            STICK_DEADZONES[0],
+            STICK_DEADZONES[STICK_DEADZONES.len() - 1],
+        );
+        self.stick_deadzone = if self.stick_deadzone.is_nan() {
+            default_stick_deadzone()
+        } else {
+            self.stick_deadzone.clamp(min, max)
+        };
+        self
+    }
+
     pub fn is_fullscreen(&self) -> bool {
         self.fullscreen
     }
@@ -85,6 +220,51 @@ impl Settings {
         self.mute
     }
 
+    /// the volume level, from 0 to 100
+    pub fn volume(&self, volume: Volume) -> u8 {
+        match volume {
+            Volume::Master => self.master_volume,
+            Volume::Music => self.music_volume,
+            Volume::Sfx => self.sfx_volume,
+        }
+    }
+
+    /// raises or lowers the volume by `steps` steps, staying between 0 and 100
+    pub fn step_volume(&mut self, volume: Volume, steps: i32) -> u8 {
+        let level = match volume {
+            Volume::Master => &mut self.master_volume,
+            Volume::Music => &mut self.music_volume,
+            Volume::Sfx => &mut self.sfx_volume,
+        };
+        *level = (*level as i32 + steps * VOLUME_STEP as i32).clamp(0, MAX_VOLUME as i32) as u8;
+        let level = *level;
+
+        self.save_settings();
+        level
+    }
+
+    /// raises the volume by a step, going back to silent after the loudest
+    pub fn cycle_volume(&mut self, volume: Volume) -> u8 {
+        if self.volume(volume) >= MAX_VOLUME {
+            self.step_volume(volume, -((MAX_VOLUME / VOLUME_STEP) as i32))
+        } else {
+            self.step_volume(volume, 1)
+        }
+    }
+
+    /// what to multiply the loudness of sounds played on `volume`'s bus by, from 0 to 1
+    pub fn gain(&self, volume: Volume) -> f32 {
+        if self.mute {
+            return 0.;
+        }
+
+        let master = self.master_volume as f32 / MAX_VOLUME as f32;
+        match volume {
+            Volume::Master => master,
+            Volume::Music | Volume::Sfx => master * self.volume(volume) as f32 / MAX_VOLUME as f32,
+        }
+    }
+
     pub fn toggle_fullscreen(&mut self) -> bool {
         self.fullscreen = !self.fullscreen;
         set_fullscreen(self.fullscreen);
@@ -92,20 +272,92 @@ impl Settings {
         self.fullscreen
     }
 
-    #[cfg(target_family = "wasm")]
+    pub fn stick_deadzone(&self) -> f32 {
+        self.stick_deadzone
+    }
+
+    pub fn is_stick_snap(&self) -> bool {
+        self.stick_snap
+    }
+
+    /// switches to the next larger deadzone, wrapping around to the smallest
+    pub fn cycle_stick_deadzone(&mut self) -> f32 {
+        self.stick_deadzone = STICK_DEADZONES
+            .into_iter()
+            .find(|deadzone| *deadzone > self.stick_deadzone + 0.001)
+            .unwrap_or(STICK_DEADZONES[0]);
+
+        self.save_settings();
+        self.stick_deadzone
+    }
+
+    /// switches to the deadzone `steps` larger or smaller, stopping at the smallest and largest
+    pub fn step_stick_deadzone(&mut self, steps: i32) -> f32 {
+        let current = STICK_DEADZONES
+            .iter()
+            .position(|deadzone| *deadzone > self.stick_deadzone - 0.001)
+            .unwrap_or(STICK_DEADZONES.len() - 1) as i32;
+        let index = (current + steps).clamp(0, STICK_DEADZONES.len() as i32 - 1);
+        self.stick_deadzone = STICK_DEADZONES[index as usize];
+
+        self.save_settings();
+        self.stick_deadzone
+    }
+
+    pub fn toggle_stick_snap(&mut self) -> bool {
+        self.stick_snap = !self.stick_snap;
+
+        self.save_settings();
+        self.stick_snap
+    }
+
+    pub fn bindings(&self) -> &Bindings {
+        &self.bindings
+    }
+
+    /// Errors with the other action the key is already bound to
+    pub fn bind_key(&mut self, action: Action, key_code: KeyCode) -> Result<(), Action> {
+        self.bindings.bind_key(action, key_code)?;
+        self.save_settings();
+        Ok(())
+    }
+
+    /// Errors with the other action the button is already bound to
+    pub fn bind_button(&mut self, action: Action, button: Button) -> Result<(), Action> {
+        self.bindings.bind_button(action, button)?;
+        self.save_settings();
+        Ok(())
+    }
+
+    pub fn reset_bindings(&mut self) {
+        self.bindings = Bindings::default();
+        self.save_settings();
+    }
+
+    #[cfg(all(target_family = "wasm", not(test)))]
     fn save_settings(&self) {
         let storage = &mut quad_storage::STORAGE.lock().unwrap();
         storage.set(MUTE, self.mute.to_string().as_str());
         storage.set(FULLSCREEN, self.fullscreen.to_string().as_str());
+        storage.set(MASTER_VOLUME, self.master_volume.to_string().as_str());
+        storage.set(MUSIC_VOLUME, self.music_volume.to_string().as_str());
+        storage.set(SFX_VOLUME, self.sfx_volume.to_string().as_str());
+        storage.set(STICK_DEADZONE, self.stick_deadzone.to_string().as_str());
+        storage.set(STICK_SNAP, self.stick_snap.to_string().as_str());
+        storage.set(BINDINGS, toml::to_string(&self.bindings).unwrap().as_str());
     }
 
-    #[cfg(not(target_family = "wasm"))]
+    #[cfg(all(not(target_family = "wasm"), not(test)))]
     fn save_settings(&self) {
         let toml = toml::to_string(self).unwrap();
         std::fs::write(Self::determine_settings_path(), toml)
             .expect("unable to write settings file");
     }
 
+    /// tests leave the player's real settings file alone
+    #[cfg(test)]
+    fn save_settings(&self) {}
+
     #[cfg(not(target_family = "wasm"))]
     fn determine_settings_path() -> PathBuf {
         let project_dirs = dir::project_dirs();
@@ -116,3 +368,76 @@ impl Settings {
         settings_path
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_volume_stays_in_range() {
+        let mut settings = Settings::default();
+        assert_eq!(settings.step_volume(Volume::Music, 1), MAX_VOLUME);
+        assert_eq!(settings.step_volume(Volume::Music, -3), 70);
+        assert_eq!(settings.step_volume(Volume::Music, -20), 0);
+        // the other buses aren't touched
+        assert_eq!(settings.volume(Volume::Master), MAX_VOLUME);
+        assert_eq!(settings.volume(Volume::Sfx), MAX_VOLUME);
+    }
+
+    #[test]
+    fn test_cycle_volume_wraps_to_silent() {
+        let mut settings = Settings::default();
+        assert_eq!(settings.cycle_volume(Volume::Sfx), 0);
+        assert_eq!(settings.cycle_volume(Volume::Sfx), VOLUME_STEP);
+    }
+
+    #[test]
+    fn test_gain() {
+        let mut settings = Settings::default();
+        assert_eq!(settings.gain(Volume::Sfx), 1.);
+
+        settings.step_volume(Volume::Master, -5);
+        settings.step_volume(Volume::Sfx, -5);
+        assert_eq!(settings.gain(Volume::Master), 0.5);
+        assert_eq!(settings.gain(Volume::Sfx), 0.25);
+        assert_eq!(settings.gain(Volume::Music), 0.5);
+
+        settings.toggle_mute();
+        assert_eq!(settings.gain(Volume::Music), 0.);
+    }
+
+    #[test]
+    fn test_unreadable_bindings_use_defaults() {
+        let settings = Settings::from_toml(
+            "mute = true\nfullscreen = false\n[bindings.keyboard]\nFire = [\"Spce\"]\n",
+        );
+        assert!(settings.is_muted());
+        assert_eq!(settings.bindings(), &Bindings::default());
+
+        let settings =
+            Settings::from_toml("mute = \"yes\"\n[bindings.keyboard]\nFire = [\"Enter\"]\n");
+        assert!(!settings.is_muted());
+        assert_eq!(
+            settings.bindings().keys(Action::Fire).collect::<Vec<_>>(),
+            [KeyCode::Enter]
+        );
+
+        let settings = Settings::from_toml("not toml");
+        assert_eq!(settings.volume(Volume::Master), MAX_VOLUME);
+    }
+
+    #[test]
+    fn test_hand_edited_values_are_clamped() {
+        let settings: Settings = toml::from_str(
+            "mute = false\nfullscreen = false\nmaster_volume = 250\nstick_deadzone = 3.0\n",
+        )
+        .unwrap();
+        let settings = settings.clamped();
+        assert_eq!(settings.volume(Volume::Master), MAX_VOLUME);
+        assert_eq!(settings.gain(Volume::Master), 1.);
+        assert_eq!(
+            settings.stick_deadzone(),
+            STICK_DEADZONES[STICK_DEADZONES.len() - 1]
+        );
+    }
+}
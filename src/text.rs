@@ -9,6 +9,7 @@ use macroquad::color::Color;
 use macroquad::text::{draw_text_ex, TextParams};
 
 use crate::context::Context;
+use crate::font;
 
 /// draw the text to the screen, simpler API than Macroquad's with a default font and enum for size
 pub fn draw_text(ctx: &mut Context, text: &str, x: f32, y: f32, size: Size, color: Color) {
@@ -18,7 +19,7 @@ pub fn draw_text(ctx: &mut Context, text: &str, x: f32, y: f32, size: Size, colo
         y,
         TextParams {
             font_size: text_size(size),
-            font: Some(&ctx.fonts.regular),
+            font: ctx.fonts.get(font::REGULAR),
             color,
             ..Default::default()
         },
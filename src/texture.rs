@@ -1,22 +1,64 @@
+use std::collections::BTreeMap;
 use std::path::Path;
 
-use macroquad::texture::{load_texture, Texture2D};
+use macroquad::miniquad::FilterMode;
+use macroquad::texture::{Image, Texture2D};
 
+use crate::asset_error::{read_asset, AssetError};
+use crate::manifest::TextureEntry;
+
+/// every texture in the asset manifest, by id
 pub struct TextureAtlas {
-    pub example: Texture2D,
+    textures: BTreeMap<String, Texture2D>,
+    /// drawn in place of textures that couldn't be loaded
+    placeholder: Texture2D,
+}
+
+impl Default for TextureAtlas {
+    /// no textures yet, they're added as they're loaded
+    fn default() -> Self {
+        Self {
+            textures: BTreeMap::new(),
+            placeholder: placeholder(),
+        }
+    }
 }
 
 impl TextureAtlas {
-    pub async fn new(base_assets_path: &Path) -> Self {
-        let example = load_texture(
-            base_assets_path
-                .join("sprites/example.png")
-                .to_str()
-                .unwrap(),
-        )
-        .await
-        .unwrap();
-        example.set_filter(macroquad::miniquad::FilterMode::Nearest);
-        Self { example }
+    pub fn insert(&mut self, id: String, texture: Texture2D) {
+        self.textures.insert(id, texture);
+    }
+
+    /// the texture with the id from the asset manifest, or a magenta checkerboard if it
+    /// couldn't be loaded
+    pub fn get(&self, id: &str) -> &Texture2D {
+        self.textures.get(id).unwrap_or(&self.placeholder)
     }
 }
+
+/// reads the texture with the id from the asset manifest
+pub async fn load(
+    id: &str,
+    base_assets_path: &Path,
+    entry: &TextureEntry,
+) -> Result<Texture2D, AssetError> {
+    let path = base_assets_path.join(&entry.path);
+    let bytes = read_asset(id, &path).await?;
+    let image = Image::from_file_with_format(&bytes, None).map_err(|e| AssetError::Decode {
+        id: id.to_string(),
+        path,
+        reason: e.to_string(),
+    })?;
+    let texture = Texture2D::from_image(&image);
+    texture.set_filter(entry.filter.into());
+    Ok(texture)
+}
+
+/// a 2x2 magenta and black checkerboard, hard to miss
+fn placeholder() -> Texture2D {
+    let magenta = [255, 0, 255, 255];
+    let black = [0, 0, 0, 255];
+    let texture = Texture2D::from_rgba8(2, 2, &[magenta, black, black, magenta].concat());
+    texture.set_filter(FilterMode::Nearest);
+    texture
+}
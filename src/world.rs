@@ -0,0 +1,718 @@
+use macroquad::math::Circle;
+use macroquad::math::Rect;
+use macroquad::math::Vec2;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::consts::VIRTUAL_HEIGHT;
+use crate::consts::VIRTUAL_WIDTH;
+use crate::level::Level;
+use crate::math::Segment;
+use crate::rng::Rng;
+
+const MOVEMENT_SPEED: f32 = 300.;
+pub const PLAYER_RADIUS: f32 = 5.;
+const PLAYER_START_POSITION: Vec2 = Vec2::new(300., 300.);
+const BULLET_RADIUS: f32 = 5.;
+const BULLET_MOVEMENT_SPEED: f32 = 200.;
+/// minimum seconds between shots, which is also the rate of autofire when holding fire
+const FIRE_COOLDOWN: f64 = 0.2;
+/// asteroids at least this tall break in two when shot, smaller ones are destroyed
+const ASTEROID_SPLIT_MIN_HEIGHT: f32 = 60.;
+/// gap left between the two halves of a split asteroid
+const ASTEROID_SPLIT_GAP: f32 = 10.;
+/// how fast the halves of a split asteroid drift apart, in pixels per second
+const ASTEROID_SPLIT_DRIFT_SPEED: f32 = 40.;
+const CRYSTAL_RADIUS: f32 = 10.;
+const CRYSTAL_MOVEMENT_SPEED: f32 = 50.;
+/// how quickly the player speeds up when warping out, in pixels per second squared
+pub const WARP_ACCELERATION: f32 = 1200.;
+
+/// what the player is doing during a single fixed update
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TickInput {
+    /// which way and how fast to move, as a fraction of the full speed; longer than 1 is capped
+    pub movement: Vec2,
+    pub fire: bool,
+}
+
+/// something that happened during a step, for the game to react to, e.g. with a sound
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WorldEvent {
+    /// a bullet was fired from the position
+    Fired(Vec2),
+    /// an asteroid was shot, at the position where the bullet hit it
+    AsteroidHit(Vec2),
+    /// the player picked up a crystal at the position
+    CrystalCollected(Vec2),
+    /// the player crashed at the position
+    Died(Vec2),
+    /// the player collected enough crystals and started warping out from the position
+    Warped(Vec2),
+}
+
+/// the state of a run, without any rendering, input or timing from macroquad, so it can be
+/// stepped headlessly
+pub struct World {
+    /// seconds of simulation since the world was created
+    pub clock: f64,
+    /// how long the current run has lasted, in seconds; stops once the player dies or warps out
+    pub run_time: f64,
+    /// how many asteroids the player has made it past
+    pub score: u32,
+    /// when the player crashed, if they have
+    pub died_at: Option<f64>,
+    /// when the player started warping out after collecting enough crystals, if they have
+    pub warped_at: Option<f64>,
+
+    pub level: Level,
+    /// used for every spawn decision
+    rng: Rng,
+    pub player_position: Vec2,
+    /// where the player was before the last step, to interpolate between when drawing
+    pub prev_player_position: Vec2,
+    pub walls: Vec<Segment>,
+    pub asteroids: Vec<Asteroid>,
+    last_spawn_asteroid_time: f64,
+    pub bullets: Vec<Bullet>,
+    /// when the player last fired, to limit the rate of fire
+    last_fire_time: f64,
+    pub crystals: Vec<Crystal>,
+    pub crystals_collected: u32,
+    last_spawn_crystal_time: f64,
+    /// what happened during the last step
+    pub events: Vec<WorldEvent>,
+}
+
+pub struct Bullet {
+    pub circle: Circle,
+    /// where it was before the last step
+    pub prev_position: Vec2,
+}
+
+pub struct Asteroid {
+    pub rect: Rect,
+    /// where it was before the last step
+    pub prev_position: Vec2,
+    /// how fast it flies to the left, in pixels per second
+    pub speed: f32,
+    /// how fast it drifts down (or up, if negative), in pixels per second
+    pub drift_speed: f32,
+    /// whether the player has made it past the asteroid, to count it only once
+    pub dodged: bool,
+}
+
+impl Asteroid {
+    /// where to draw it, `alpha` of the way from its previous position to its current one
+    pub fn interpolated_rect(&self, alpha: f32) -> Rect {
+        let position = self.prev_position.lerp(self.rect.point(), alpha);
+        Rect::new(position.x, position.y, self.rect.w, self.rect.h)
+    }
+
+    /// what's left of the asteroid after getting shot: large ones split into a top and bottom
+    /// half that drift apart, small ones are destroyed
+    fn split(&self) -> Vec<Asteroid> {
+        let r = self.rect;
+        if r.h < ASTEROID_SPLIT_MIN_HEIGHT {
+            return vec![];
+        }
+
+        let half_h = ((r.h - ASTEROID_SPLIT_GAP) / 2.).floor();
+        vec![
+            Asteroid {
+                rect: Rect::new(r.x, r.y, r.w, half_h),
+                prev_position: self.prev_position,
+                speed: self.speed,
+                drift_speed: self.drift_speed - ASTEROID_SPLIT_DRIFT_SPEED,
+                dodged: self.dodged,
+            },
+            Asteroid {
+                rect: Rect::new(r.x, r.bottom() - half_h, r.w, half_h),
+                prev_position: self.prev_position + Vec2::new(0., r.h - half_h),
+                speed: self.speed,
+                drift_speed: self.drift_speed + ASTEROID_SPLIT_DRIFT_SPEED,
+                dodged: self.dodged,
+            },
+        ]
+    }
+}
+
+/// a warp crystal, enough of which lets the player warp out and complete the level
+pub struct Crystal {
+    pub circle: Circle,
+    /// where it was before the last step
+    pub prev_position: Vec2,
+}
+
+impl World {
+    /// starts a run of the level; runs with the same seed and inputs play out the same
+    pub fn new(level: Level, seed: u64) -> Self {
+        let walls = level.walls.clone();
+
+        Self {
+            clock: 0.,
+            run_time: 0.,
+            score: 0,
+            died_at: None,
+            warped_at: None,
+            level,
+            rng: Rng::new(seed),
+            player_position: PLAYER_START_POSITION,
+            prev_player_position: PLAYER_START_POSITION,
+            walls,
+            asteroids: vec![],
+            last_spawn_asteroid_time: -f64::INFINITY,
+            bullets: vec![],
+            last_fire_time: -f64::INFINITY,
+            crystals: vec![],
+            crystals_collected: 0,
+            last_spawn_crystal_time: 0.,
+            events: vec![],
+        }
+    }
+
+    /// advances the simulation by `dt` seconds
+    pub fn step(&mut self, input: &TickInput, dt: f32) {
+        self.clock += dt as f64;
+        self.events.clear();
+        self.save_prev_positions();
+
+        if let Some(warped_at) = self.warped_at {
+            // speed off to the right, leaving the asteroids behind
+            let warp_time = self.clock - warped_at;
+            self.player_position.x += WARP_ACCELERATION * warp_time as f32 * dt;
+            self.asteroid_movement(dt);
+            return;
+        }
+
+        if self.died_at.is_some() {
+            return;
+        }
+
+        self.run_time += dt as f64;
+        self.player_movement(input, dt);
+        self.player_attack(input, dt);
+        self.asteroid_movement(dt);
+        self.crystal_movement(dt);
+        self.bullet_collisions();
+        self.cull_offscreen();
+
+        let player_circle = Circle::new(
+            self.player_position.x,
+            self.player_position.y,
+            PLAYER_RADIUS,
+        );
+        // check for collisions
+        for a in &mut self.asteroids {
+            if player_circle.overlaps_rect(&a.rect) {
+                self.died_at = Some(self.clock);
+                self.events.push(WorldEvent::Died(self.player_position));
+                return;
+            }
+
+            if !a.dodged && a.rect.right() < player_circle.x - player_circle.r {
+                a.dodged = true;
+                self.score += 1;
+            }
+        }
+
+        let crystals_before = self.crystals.len();
+        let events = &mut self.events;
+        self.crystals.retain(|c| {
+            let collected = player_circle.overlaps(&c.circle);
+            if collected {
+                events.push(WorldEvent::CrystalCollected(c.circle.point()));
+            }
+            !collected
+        });
+        self.crystals_collected += (crystals_before - self.crystals.len()) as u32;
+        if self.crystals_collected >= self.level.crystal_quota {
+            self.warped_at = Some(self.clock);
+            self.events.push(WorldEvent::Warped(self.player_position));
+            return;
+        }
+
+        let elapsed = self.clock;
+        let progress = self.run_time as f32 / self.level.duration;
+        let spawn_interval = self.level.spawn_interval.at(progress) as f64;
+        if elapsed > self.last_spawn_asteroid_time + spawn_interval {
+            self.spawn_asteroid();
+            self.last_spawn_asteroid_time = elapsed;
+        }
+        if elapsed > self.last_spawn_crystal_time + self.level.crystal_spawn_interval as f64 {
+            self.spawn_crystal();
+            self.last_spawn_crystal_time = elapsed;
+        }
+    }
+
+    /// remembers where everything is before it moves, for drawing to interpolate from
+    fn save_prev_positions(&mut self) {
+        self.prev_player_position = self.player_position;
+        for a in &mut self.asteroids {
+            a.prev_position = a.rect.point();
+        }
+        for b in &mut self.bullets {
+            b.prev_position = b.circle.point();
+        }
+        for c in &mut self.crystals {
+            c.prev_position = c.circle.point();
+        }
+    }
+
+    fn player_movement(&mut self, input: &TickInput, delta: f32) {
+        let movement_vec = input.movement.clamp_length_max(1.);
+        if movement_vec != Vec2::new(0., 0.) {
+            self.player_position += MOVEMENT_SPEED * delta * movement_vec;
+            self.player_position = self
+                .player_position
+                .clamp(Vec2::default(), Vec2::new(VIRTUAL_WIDTH, VIRTUAL_HEIGHT));
+        }
+    }
+
+    fn player_attack(&mut self, input: &TickInput, delta: f32) {
+        for b in &mut self.bullets {
+            b.circle.x += BULLET_MOVEMENT_SPEED * delta;
+        }
+        // keeps firing while held, as fast as the cooldown allows
+        let now = self.clock;
+        if input.fire && now > self.last_fire_time + FIRE_COOLDOWN {
+            self.last_fire_time = now;
+            let circle = Circle::new(
+                self.player_position.x + 10.,
+                self.player_position.y,
+                BULLET_RADIUS,
+            );
+            self.bullets.push(Bullet {
+                circle,
+                prev_position: circle.point(),
+            });
+            self.events.push(WorldEvent::Fired(circle.point()));
+        }
+    }
+
+    fn asteroid_movement(&mut self, delta: f32) {
+        for a in &mut self.asteroids {
+            a.rect.x -= a.speed * delta;
+            a.rect.y += a.drift_speed * delta;
+        }
+    }
+
+    /// bullets break apart the first asteroid they hit
+    fn bullet_collisions(&mut self) {
+        let mut fragments = vec![];
+        self.bullets.retain(|b| {
+            match self
+                .asteroids
+                .iter()
+                .position(|a| b.circle.overlaps_rect(&a.rect))
+            {
+                Some(hit) => {
+                    fragments.extend(self.asteroids.swap_remove(hit).split());
+                    self.events.push(WorldEvent::AsteroidHit(b.circle.point()));
+                    false
+                }
+                None => true,
+            }
+        });
+        self.asteroids.extend(fragments);
+    }
+
+    /// removes anything that has left the screen for good, so they don't pile up
+    fn cull_offscreen(&mut self) {
+        let screen = Rect::new(0., 0., VIRTUAL_WIDTH, VIRTUAL_HEIGHT);
+        self.bullets
+            .retain(|b| b.circle.x - b.circle.r < screen.right());
+        // asteroids and crystals spawn past the right edge and fly left, so only that side is
+        // checked for them, but split asteroids can drift off the top or bottom
+        self.asteroids.retain(|a| {
+            a.rect.right() > 0. && a.rect.bottom() > 0. && a.rect.top() < screen.bottom()
+        });
+        self.crystals.retain(|c| c.circle.x + c.circle.r > 0.);
+    }
+
+    fn crystal_movement(&mut self, delta: f32) {
+        for c in &mut self.crystals {
+            c.circle.x -= CRYSTAL_MOVEMENT_SPEED * delta;
+        }
+    }
+
+    fn spawn_crystal(&mut self) {
+        // random y, kept far enough from the edges to be reachable
+        let ratio_y = self.rng.ratio();
+        let margin = CRYSTAL_RADIUS * 2.;
+
+        let circle = Circle::new(
+            VIRTUAL_WIDTH + CRYSTAL_RADIUS,
+            margin + (ratio_y * (VIRTUAL_HEIGHT - 2. * margin)).floor(),
+            CRYSTAL_RADIUS,
+        );
+        self.crystals.push(Crystal {
+            circle,
+            prev_position: circle.point(),
+        })
+    }
+
+    fn spawn_asteroid(&mut self) {
+        // random y
+        let ratio_y = self.rng.ratio();
+        // random size
+        let ratio_w = self.rng.ratio();
+        let ratio_h = self.rng.ratio();
+        // random speed
+        let ratio_speed = self.rng.ratio();
+
+        let rect = Rect::new(
+            VIRTUAL_WIDTH,
+            (ratio_y * VIRTUAL_HEIGHT).floor(),
+            self.level.asteroid_width.sample(ratio_w).floor(),
+            self.level.asteroid_height.sample(ratio_h).floor(),
+        );
+        self.asteroids.push(Asteroid {
+            rect,
+            prev_position: rect.point(),
+            speed: self.level.asteroid_speed.sample(ratio_speed),
+            drift_speed: 0.,
+            dodged: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::{Curve, Range};
+
+    const DT: f32 = 1. / 120.;
+
+    fn test_level() -> Level {
+        Level {
+            id: "test".to_string(),
+            name: "Test".to_string(),
+            duration: 30.,
+            spawn_interval: Curve {
+                start: 1.,
+                end: 0.5,
+            },
+            crystal_spawn_interval: 3.,
+            asteroid_width: Range { min: 20., max: 80. },
+            asteroid_height: Range {
+                min: 20.,
+                max: 120.,
+            },
+            asteroid_speed: Range {
+                min: 100.,
+                max: 300.,
+            },
+            crystal_quota: 5,
+            walls: vec![],
+            music: None,
+            assets: Default::default(),
+        }
+    }
+
+    /// a world with nothing spawning on its own, to set up exact situations
+    fn empty_world() -> World {
+        let mut level = test_level();
+        level.spawn_interval = Curve {
+            start: f32::INFINITY,
+            end: f32::INFINITY,
+        };
+        level.crystal_spawn_interval = f32::INFINITY;
+        World::new(level, 0)
+    }
+
+    fn asteroid_at(rect: Rect) -> Asteroid {
+        Asteroid {
+            rect,
+            prev_position: rect.point(),
+            speed: 0.,
+            drift_speed: 0.,
+            dodged: false,
+        }
+    }
+
+    #[test]
+    fn test_same_seed_and_input_same_run() {
+        let mut a = World::new(test_level(), 7);
+        let mut b = World::new(test_level(), 7);
+        for tick in 0..3_000 {
+            let input = TickInput {
+                movement: if tick % 240 < 120 {
+                    Vec2::NEG_Y
+                } else {
+                    Vec2::Y
+                },
+                fire: true,
+            };
+            a.step(&input, DT);
+            b.step(&input, DT);
+        }
+        assert_eq!(a.died_at, b.died_at);
+        assert_eq!(a.score, b.score);
+        assert_eq!(a.player_position, b.player_position);
+        assert_eq!(a.asteroids.len(), b.asteroids.len());
+        for (a, b) in a.asteroids.iter().zip(&b.asteroids) {
+            assert_eq!(a.rect, b.rect);
+        }
+    }
+
+    #[test]
+    fn test_movement_speed() {
+        let mut world = empty_world();
+        let start = world.player_position;
+        let input = TickInput {
+            movement: Vec2::X,
+            ..Default::default()
+        };
+        for _ in 0..120 {
+            world.step(&input, DT);
+        }
+        let moved = world.player_position.x - start.x;
+        assert!((moved - MOVEMENT_SPEED).abs() < 0.1, "{moved}");
+    }
+
+    #[test]
+    fn test_partial_movement_is_slower() {
+        let mut world = empty_world();
+        let start = world.player_position;
+        let input = TickInput {
+            movement: Vec2::new(0., 0.5),
+            ..Default::default()
+        };
+        for _ in 0..120 {
+            world.step(&input, DT);
+        }
+        let moved = world.player_position.y - start.y;
+        assert!((moved - MOVEMENT_SPEED / 2.).abs() < 0.1, "{moved}");
+    }
+
+    #[test]
+    fn test_movement_is_capped_at_full_speed() {
+        let mut world = empty_world();
+        let start = world.player_position;
+        let input = TickInput {
+            movement: Vec2::new(3., 4.),
+            ..Default::default()
+        };
+        for _ in 0..60 {
+            world.step(&input, DT);
+        }
+        let moved = world.player_position.distance(start);
+        assert!((moved - MOVEMENT_SPEED / 2.).abs() < 0.1, "{moved}");
+    }
+
+    #[test]
+    fn test_player_stays_on_screen() {
+        let mut world = empty_world();
+        let input = TickInput {
+            movement: Vec2::new(-1., -1.),
+            ..Default::default()
+        };
+        for _ in 0..1_000 {
+            world.step(&input, DT);
+        }
+        assert_eq!(world.player_position, Vec2::ZERO);
+    }
+
+    #[test]
+    fn test_crash_into_asteroid() {
+        let mut world = empty_world();
+        let p = world.player_position;
+        world.asteroids.push(Asteroid {
+            speed: 100.,
+            ..asteroid_at(Rect::new(p.x + 50., p.y - 10., 20., 20.))
+        });
+        for _ in 0..120 {
+            world.step(&TickInput::default(), DT);
+        }
+        let died_at = world.died_at.expect("player should have crashed");
+        // the gap closes in just under half a second
+        assert!((0.4..0.5).contains(&died_at), "{died_at}");
+        // nothing moves on after the crash, including the run time
+        let run_time = world.run_time;
+        world.step(&TickInput::default(), DT);
+        assert_eq!(world.run_time, run_time);
+    }
+
+    #[test]
+    fn test_dodge_scores_once() {
+        let mut world = empty_world();
+        let p = world.player_position;
+        world.asteroids.push(Asteroid {
+            speed: 200.,
+            ..asteroid_at(Rect::new(p.x + 50., p.y + 20., 20., 20.))
+        });
+        for _ in 0..240 {
+            world.step(&TickInput::default(), DT);
+        }
+        assert_eq!(world.died_at, None);
+        assert_eq!(world.score, 1);
+    }
+
+    #[test]
+    fn test_bullet_splits_large_asteroid() {
+        let mut world = empty_world();
+        let p = world.player_position;
+        world
+            .asteroids
+            .push(asteroid_at(Rect::new(p.x + 100., p.y - 50., 20., 100.)));
+        let fire = TickInput {
+            fire: true,
+            ..Default::default()
+        };
+        world.step(&fire, DT);
+        for _ in 0..120 {
+            world.step(&TickInput::default(), DT);
+        }
+        assert!(world.bullets.is_empty());
+        assert_eq!(world.asteroids.len(), 2);
+        let (top, bottom) = (&world.asteroids[0], &world.asteroids[1]);
+        assert!(top.drift_speed < 0. && bottom.drift_speed > 0.);
+    }
+
+    #[test]
+    fn test_bullet_destroys_small_asteroid() {
+        let mut world = empty_world();
+        let p = world.player_position;
+        world
+            .asteroids
+            .push(asteroid_at(Rect::new(p.x + 100., p.y - 10., 20., 20.)));
+        let fire = TickInput {
+            fire: true,
+            ..Default::default()
+        };
+        world.step(&fire, DT);
+        for _ in 0..120 {
+            world.step(&TickInput::default(), DT);
+        }
+        assert!(world.asteroids.is_empty());
+    }
+
+    #[test]
+    fn test_fire_cooldown() {
+        let mut world = empty_world();
+        let fire = TickInput {
+            fire: true,
+            ..Default::default()
+        };
+        // one second of holding fire
+        for _ in 0..120 {
+            world.step(&fire, DT);
+        }
+        assert_eq!(world.bullets.len(), (1. / FIRE_COOLDOWN) as usize);
+    }
+
+    #[test]
+    fn test_crystal_quota_warps_out() {
+        let mut world = empty_world();
+        let p = world.player_position;
+        for _ in 0..world.level.crystal_quota {
+            let circle = Circle::new(p.x, p.y, CRYSTAL_RADIUS);
+            world.crystals.push(Crystal {
+                circle,
+                prev_position: circle.point(),
+            });
+        }
+        world.step(&TickInput::default(), DT);
+        assert_eq!(world.crystals_collected, world.level.crystal_quota);
+        assert!(world.warped_at.is_some());
+
+        // the player speeds off to the right and can't crash anymore
+        world
+            .asteroids
+            .push(asteroid_at(Rect::new(p.x, p.y - 10., 20., 20.)));
+        for _ in 0..120 {
+            world.step(&TickInput::default(), DT);
+        }
+        assert_eq!(world.died_at, None);
+        assert!(world.player_position.x > p.x + 100.);
+    }
+
+    /// steps the world with the input for `ticks` ticks, gathering every event along the way
+    fn step_collecting_events(
+        world: &mut World,
+        input: &TickInput,
+        ticks: usize,
+    ) -> Vec<WorldEvent> {
+        let mut events = vec![];
+        for _ in 0..ticks {
+            world.step(input, DT);
+            events.extend(world.events.iter().copied());
+        }
+        events
+    }
+
+    #[test]
+    fn test_crash_event() {
+        let mut world = empty_world();
+        let p = world.player_position;
+        world.asteroids.push(Asteroid {
+            speed: 100.,
+            ..asteroid_at(Rect::new(p.x + 50., p.y - 10., 20., 20.))
+        });
+        let events = step_collecting_events(&mut world, &TickInput::default(), 120);
+        assert!(matches!(events[..], [WorldEvent::Died(_)]));
+    }
+
+    #[test]
+    fn test_fire_and_hit_events() {
+        let mut world = empty_world();
+        let p = world.player_position;
+        world
+            .asteroids
+            .push(asteroid_at(Rect::new(p.x + 100., p.y - 10., 20., 20.)));
+        let fire = TickInput {
+            fire: true,
+            ..Default::default()
+        };
+        world.step(&fire, DT);
+        assert!(matches!(world.events[..], [WorldEvent::Fired(_)]));
+
+        let events = step_collecting_events(&mut world, &TickInput::default(), 120);
+        assert!(matches!(events[..], [WorldEvent::AsteroidHit(_)]));
+    }
+
+    #[test]
+    fn test_crystal_and_warp_events() {
+        let mut world = empty_world();
+        let p = world.player_position;
+        for _ in 0..world.level.crystal_quota {
+            let circle = Circle::new(p.x, p.y, CRYSTAL_RADIUS);
+            world.crystals.push(Crystal {
+                circle,
+                prev_position: circle.point(),
+            });
+        }
+        world.step(&TickInput::default(), DT);
+        let collected = world
+            .events
+            .iter()
+            .filter(|e| matches!(e, WorldEvent::CrystalCollected(_)))
+            .count();
+        assert_eq!(collected, world.level.crystal_quota as usize);
+        assert!(matches!(world.events.last(), Some(WorldEvent::Warped(_))));
+
+        // events only last for the step they happened in
+        world.step(&TickInput::default(), DT);
+        assert!(world.events.is_empty());
+    }
+
+    #[test]
+    fn test_long_run_culls_offscreen_objects() {
+        let mut world = World::new(test_level(), 3);
+        // keep out of the way along the top edge until something hits
+        let input = TickInput {
+            movement: Vec2::NEG_Y,
+            fire: true,
+        };
+        for _ in 0..(120 * 60) {
+            world.step(&input, DT);
+            assert!(world.asteroids.len() < 50);
+            assert!(world.bullets.len() < 50);
+            assert!(world.crystals.len() < 50);
+            if world.died_at.is_some() || world.warped_at.is_some() {
+                break;
+            }
+        }
+    }
+}